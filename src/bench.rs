@@ -0,0 +1,132 @@
+use crate::{build, Context};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+/// Number of timed runs of every benchmark, after an untimed warm-up run.
+const BENCH_RUNS: usize = 5;
+
+/// Run every benchmark binary of the `artifacts` from `current_dir`, and
+/// print the median of its run times along with the change against the
+/// previous run, which is saved as the baseline in `target/bench`.
+pub fn main(current_dir: &Path, artifacts: &build::Artifacts) -> anyhow::Result<()> {
+    let benches = &artifacts.benches;
+    if benches.is_empty() {
+        println!("No benchmarks found in `benches/`.");
+        return Ok(());
+    }
+
+    // Workspace members keep their baselines apart, under their path in the
+    // workspace.
+    let mut bench_dir = artifacts.target_dir.join("bench");
+    if let Some(member_dir) = artifacts
+        .target_dir
+        .parent()
+        .and_then(|workspace_root| artifacts.project_root.strip_prefix(workspace_root).ok())
+    {
+        bench_dir.push(member_dir);
+    }
+    fs::create_dir_all(&bench_dir)
+        .with_context(|| format!("Failed to create directory {}!", bench_dir.display()))?;
+    let baseline_path = bench_dir.join("baseline.json");
+    let mut baseline = read_baseline(&baseline_path)?;
+
+    for (name, binary) in benches {
+        let time = measure(binary, current_dir)
+            .with_context(|| format!("Failed to run benchmark `{name}`!"))?;
+        let change = match baseline.get(name) {
+            Some(&previous) => format!(", {} vs baseline", change(previous, time)),
+            None => String::new(),
+        };
+        println!(
+            "bench {name} ... {:?} (median of {BENCH_RUNS} runs{change})",
+            Duration::from_nanos(time)
+        );
+        baseline.insert(name.clone(), time);
+    }
+
+    fs::write(
+        &baseline_path,
+        serde_json::to_string_pretty(&baseline)? + "\n",
+    )
+    .with_context(|| format!("Failed to save baseline {}!", baseline_path.display()))?;
+
+    Ok(())
+}
+
+/// Median run time of `binary`, in nanoseconds.
+fn measure(binary: &Path, current_dir: &Path) -> anyhow::Result<u64> {
+    let mut times = Vec::with_capacity(BENCH_RUNS);
+    for run in 0..=BENCH_RUNS {
+        let start = Instant::now();
+        let status = Command::new(binary)
+            .current_dir(current_dir)
+            .stdout(Stdio::null())
+            .status()
+            .with_context(|| format!("Couldn't excecute benchmark {}", binary.display()))?;
+        let time = start.elapsed();
+        anyhow::ensure!(
+            status.success(),
+            format!("Benchmark {} exited with {status}!", binary.display())
+        );
+
+        // The first run only warms up caches.
+        if run > 0 {
+            times.push(u64::try_from(time.as_nanos()).unwrap_or(u64::MAX));
+        }
+    }
+    times.sort_unstable();
+
+    Ok(times[times.len() / 2])
+}
+
+/// Saved run times of every benchmark, in nanoseconds, by name.
+fn read_baseline(baseline_path: &Path) -> anyhow::Result<BTreeMap<String, u64>> {
+    if !baseline_path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let baseline = fs::read_to_string(baseline_path)
+        .with_context(|| format!("Failed to read baseline {}!", baseline_path.display()))?;
+    serde_json::from_str(&baseline)
+        .with_context(|| format!("Failed to parse baseline {}!", baseline_path.display()))
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn change(previous: u64, time: u64) -> String {
+    if previous == 0 {
+        return "n/a".to_string();
+    }
+
+    format!(
+        "{:+.2}%",
+        (time as f64 - previous as f64) * 100.0 / previous as f64
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proper_change() -> anyhow::Result<()> {
+        for (previous, time, expected) in [
+            (100, 110, "+10.00%"),
+            (200, 150, "-25.00%"),
+            (100, 100, "+0.00%"),
+            (0, 100, "n/a"),
+        ] {
+            let change = change(previous, time);
+            anyhow::ensure!(
+                change == expected,
+                format!("Got unexpected change {change} from {previous} to {time}!")
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -1,14 +1,531 @@
-use crate::Context;
+use crate::{
+    build_script, cache, cfg, diagnostic, manifest,
+    message::Reporter,
+    modules,
+    run::Runner,
+    sanitizer::{self, Sanitizer},
+    workspace, Context,
+};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, BTreeSet, HashSet},
     ffi::OsString,
     fs,
-    path::{Path, PathBuf},
-    process::Command,
+    io::Write,
+    path::{Component, Path, PathBuf},
+    process::{Command, ExitStatus, Stdio},
     str::FromStr,
 };
 
-pub fn main(current_dir: &Path) -> anyhow::Result<()> {
+pub const CPP_COMPILER: &str = "g++";
+const C_COMPILER: &str = "gcc";
+
+/// Extensions of source files compiled as C++.
+pub const CPP_EXTENSIONS: [&str; 4] = ["cpp", "cc", "cxx", "c++"];
+/// Extensions of source files compiled as C.
+const C_EXTENSIONS: [&str; 1] = ["c"];
+
+/// The main project file, which is left out when linking benchmarks and
+/// examples.
+const MAIN_SRC_FILE: &str = "src/main.cpp";
+
+/// The build script run before building the project, unless another one is
+/// configured with `[build] script`.
+const BUILD_SCRIPT: &str = "build.cpp";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Language {
+    C,
+    Cpp,
+}
+
+impl Language {
+    /// Language of a source file, judging by its extension.
+    fn of(src_file: &Path) -> Option<Self> {
+        let ext = src_file.extension()?.to_str()?;
+
+        if CPP_EXTENSIONS.contains(&ext) || modules::INTERFACE_EXTENSIONS.contains(&ext) {
+            Some(Self::Cpp)
+        } else if C_EXTENSIONS.contains(&ext) {
+            Some(Self::C)
+        } else {
+            None
+        }
+    }
+}
+
+/// Options from the command line affecting how a project is built.
+#[derive(Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Options {
+    /// Write a `compile_commands.json` at the project root.
+    pub compile_commands: bool,
+    /// Turn every warning into an error, regardless of the manifest.
+    pub deny_warnings: bool,
+    /// Instrument the build with these sanitizers.
+    pub sanitizers: BTreeSet<Sanitizer>,
+    /// Instrument the build to record line coverage.
+    pub coverage: bool,
+    pub profile: manifest::Profile,
+    /// Benchmarks in `benches/` to also build.
+    pub benches: Programs,
+    /// Examples in `examples/` to also build.
+    pub examples: Programs,
+    /// Target triple to cross-compile for, instead of the host.
+    pub target: Option<String>,
+    /// Command to run the built binaries through, instead of the manifest
+    /// `runner`.
+    pub runner: Option<Vec<String>>,
+    /// Workspace member to build, instead of the project containing the
+    /// current directory, or every member at the workspace root.
+    pub package: Option<String>,
+    /// Command to prefix compiler invocations with, instead of the manifest
+    /// `compiler-launcher`.
+    pub compiler_launcher: Option<Vec<String>>,
+    /// Directory of the object cache used without a compiler launcher.
+    pub cache_dir: Option<PathBuf>,
+    /// Only compile the source files, without linking any binary.
+    pub check: bool,
+    /// Archive the objects of a library project into `lib<NAME>.a`.
+    pub archive: bool,
+}
+
+/// Which of the programs in a directory such as `benches/` or `examples/`
+/// are built. Each program is a single source file, linked along with every
+/// project source file except `src/main.cpp`.
+#[derive(Default)]
+pub enum Programs {
+    #[default]
+    None,
+    All,
+    Named(String),
+}
+
+/// Paths of the binaries built by `build::main` for a project.
+pub struct Artifacts {
+    pub project_root: PathBuf,
+    /// The project name, from its manifest.
+    pub name: String,
+    /// The `target/` directory, which is shared by every member of a
+    /// workspace.
+    pub target_dir: PathBuf,
+    /// Directory of the object files the binaries were linked from.
+    pub objects_dir: PathBuf,
+    /// The project binary, unless the project is a library, without
+    /// `src/main.cpp`.
+    pub binary: Option<PathBuf>,
+    /// The static library archived from the objects of a library project,
+    /// if asked for.
+    pub library: Option<PathBuf>,
+    /// Benchmark binaries, by name.
+    pub benches: BTreeMap<String, PathBuf>,
+    /// Example binaries, by name.
+    pub examples: BTreeMap<String, PathBuf>,
+    /// How the binaries are run.
+    pub runner: Runner,
+}
+
+/// The compilers used to build a project, and the flags given to them on top
+/// of the files to compile or link.
+struct Toolchain {
+    c_compiler: String,
+    /// C++ compiler, which is also used for linking.
+    cpp_compiler: String,
+    /// Flags for compiling C sources.
+    c_flags: Vec<String>,
+    /// Flags for compiling C++ sources.
+    cpp_flags: Vec<String>,
+    /// Flags for compiling any source.
+    compile_flags: Vec<String>,
+    /// Flags for linking.
+    link_flags: Vec<String>,
+    /// Flags for linking given after the object files, such as libraries.
+    link_libs: Vec<String>,
+    /// The C++20 modules of the project, if it has any.
+    modules: Option<modules::Interfaces>,
+    /// Source files belonging to a named C++20 module.
+    module_units: BTreeSet<PathBuf>,
+    /// Header included first in every C++ source file, except for module
+    /// units, such as the one of a precompiled header.
+    forced_include: Option<PathBuf>,
+    /// Command compilers are run through, such as `ccache`.
+    compiler_launcher: Vec<String>,
+    /// Cache of object files, used when there's no compiler launcher.
+    object_cache: Option<cache::ObjectCache>,
+    /// Tool archiving the objects of libraries.
+    ar: String,
+    /// Tool splitting the debug information of binaries and stripping them.
+    objcopy: String,
+    /// What is stripped from the linked binaries.
+    strip: manifest::Strip,
+    /// Move the debug information of the linked binaries into `.debug` files.
+    split_debuginfo: bool,
+}
+
+impl Default for Toolchain {
+    fn default() -> Self {
+        Self {
+            c_compiler: C_COMPILER.to_string(),
+            cpp_compiler: CPP_COMPILER.to_string(),
+            c_flags: Vec::new(),
+            cpp_flags: Vec::new(),
+            compile_flags: Vec::new(),
+            link_flags: Vec::new(),
+            link_libs: Vec::new(),
+            modules: None,
+            module_units: BTreeSet::new(),
+            forced_include: None,
+            compiler_launcher: Vec::new(),
+            object_cache: None,
+            ar: "ar".to_string(),
+            objcopy: "objcopy".to_string(),
+            strip: manifest::Strip::None,
+            split_debuginfo: false,
+        }
+    }
+}
+
+impl Toolchain {
+    fn new(
+        manifest: &manifest::Manifest,
+        build_config: &manifest::BuildConfig,
+        options: &Options,
+    ) -> Self {
+        let mut toolchain = Self::default();
+
+        if let Some(triple) = &options.target {
+            let triple_config = manifest.triples.get(triple).cloned().unwrap_or_default();
+            // Cross compilers are usually prefixed with their target triple.
+            toolchain.cpp_compiler = triple_config
+                .compiler
+                .unwrap_or_else(|| format!("{triple}-{CPP_COMPILER}"));
+            toolchain.c_compiler = triple_config
+                .c_compiler
+                .unwrap_or_else(|| format!("{triple}-{C_COMPILER}"));
+            toolchain.ar = format!("{triple}-ar");
+            toolchain.objcopy = format!("{triple}-objcopy");
+            if let Some(sysroot) = triple_config.sysroot {
+                toolchain.compile_flags.push(format!("--sysroot={sysroot}"));
+                toolchain.link_flags.push(format!("--sysroot={sysroot}"));
+            }
+            toolchain.compile_flags.extend(triple_config.flags);
+            toolchain.link_flags.extend(triple_config.link_flags);
+        }
+        toolchain.compile_flags.extend(package_defines(manifest));
+
+        let profile = manifest.profiles.get(options.profile);
+        if let Some(opt_level) = &profile.opt_level {
+            toolchain.compile_flags.push(format!("-O{opt_level}"));
+        }
+        if profile.debug {
+            toolchain.compile_flags.push("-g".to_string());
+        }
+        if !profile.debug_assertions {
+            toolchain.compile_flags.push("-DNDEBUG".to_string());
+        }
+        // Objects only carry the compiler's intermediate representation with
+        // LTO, so the linker has to be told to optimize them too.
+        let lto_flags = lto_flags(profile.lto, &toolchain.cpp_compiler);
+        toolchain.compile_flags.extend(lto_flags.iter().cloned());
+        toolchain.link_flags.extend(lto_flags);
+        if let Some(linker) = profile.linker {
+            toolchain
+                .link_flags
+                .push(format!("-fuse-ld={}", linker.name()));
+        }
+        toolchain.strip = profile.strip;
+        toolchain.split_debuginfo = profile.split_debuginfo;
+
+        if let Some(c_standard) = &build_config.c_standard {
+            toolchain.c_flags.push(format!("-std={c_standard}"));
+        }
+
+        toolchain.compile_flags.extend(lint_flags(
+            &manifest.lints,
+            options.deny_warnings || manifest.lints.deny_warnings,
+        ));
+
+        let sanitizer_flags = sanitizer::flags(&options.sanitizers);
+        toolchain
+            .compile_flags
+            .extend(sanitizer_flags.iter().cloned());
+        toolchain.link_flags.extend(sanitizer_flags);
+
+        if options.coverage {
+            toolchain.compile_flags.push("--coverage".to_string());
+            toolchain.link_flags.push("--coverage".to_string());
+        }
+
+        toolchain.compiler_launcher = options
+            .compiler_launcher
+            .clone()
+            .or_else(|| build_config.compiler_launcher.clone())
+            .unwrap_or_default();
+        // Coverage notes are written beside the object files, but aren't
+        // cached along with them.
+        if toolchain.compiler_launcher.is_empty() && !options.coverage {
+            toolchain.object_cache = options.cache_dir.clone().map(cache::ObjectCache::new);
+        }
+
+        toolchain
+    }
+
+    /// Add the flags a build script asked for.
+    fn extend(&mut self, output: &build_script::Output) {
+        for include_dir in &output.include_dirs {
+            self.compile_flags
+                .push(format!("-I{}", include_dir.display()));
+        }
+        for define in &output.defines {
+            self.compile_flags.push(format!("-D{define}"));
+        }
+        for link_search_dir in &output.link_search_dirs {
+            self.link_flags
+                .push(format!("-L{}", link_search_dir.display()));
+        }
+        for link_lib in &output.link_libs {
+            self.link_libs.push(format!("-l{link_lib}"));
+        }
+    }
+
+    /// Scan the `src_files` of the project at `project_root` for C++20
+    /// modules, and if there are any, compile with them, storing their
+    /// compiled interfaces in `modules_dir`.
+    ///
+    /// Returns the order the source files have to be compiled in.
+    fn use_modules(
+        &mut self,
+        project_root: &Path,
+        src_files: &BTreeSet<PathBuf>,
+        modules_dir: &Path,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        let modules = modules::scan(project_root, src_files)?;
+        self.module_units = modules.units;
+        if !modules.interfaces.is_empty() {
+            let interfaces = modules::Interfaces {
+                modules: modules.interfaces,
+                dir: modules_dir.to_path_buf(),
+                precompiled: is_clang(&self.cpp_compiler),
+            };
+            self.cpp_flags.extend(interfaces.flags(project_root)?);
+            self.modules = Some(interfaces);
+            // Preprocessing doesn't reveal the imported interfaces an object
+            // file depends on.
+            self.object_cache = None;
+        }
+
+        Ok(modules.build_order)
+    }
+
+    /// The interface the module interface unit `src_file` is precompiled
+    /// into, apart from its object file, if the compiler is Clang.
+    fn precompiled_interface(&self, src_file: &Path) -> Option<PathBuf> {
+        let interfaces = self
+            .modules
+            .as_ref()
+            .filter(|interfaces| interfaces.precompiled)?;
+
+        interfaces
+            .modules
+            .get(src_file)
+            .map(|module| interfaces.file(module))
+    }
+
+    /// Run the `compiler` command through the compiler launcher, if any.
+    fn launch(&self, compiler: Command) -> Command {
+        let Some((launcher, launcher_args)) = self.compiler_launcher.split_first() else {
+            return compiler;
+        };
+
+        let mut command = Command::new(launcher);
+        command
+            .args(launcher_args)
+            .arg(compiler.get_program())
+            .args(compiler.get_args());
+
+        command
+    }
+
+    fn compiler(&self, language: Language) -> &str {
+        match language {
+            Language::C => &self.c_compiler,
+            Language::Cpp => &self.cpp_compiler,
+        }
+    }
+
+    fn flags(&self, language: Language) -> &[String] {
+        match language {
+            Language::C => &self.c_flags,
+            Language::Cpp => &self.cpp_flags,
+        }
+    }
+}
+
+/// Whether `compiler` is Clang, even if it's named like GCC, as on macOS.
+pub fn is_clang(compiler: &str) -> bool {
+    Command::new(compiler)
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains("clang"))
+}
+
+/// Flags enabling link-time optimization with `compiler`, both when compiling
+/// and linking.
+fn lto_flags(lto: manifest::Lto, compiler: &str) -> Vec<String> {
+    let flags: &[&str] = match lto {
+        manifest::Lto::Off => &[],
+        manifest::Lto::Thin if is_clang(compiler) => &["-flto=thin"],
+        manifest::Lto::Full if is_clang(compiler) => &["-flto=full"],
+        // GCC optimizes the program in parallel partitions by default, much
+        // like Clang's ThinLTO, unless told to keep it in a single one.
+        manifest::Lto::Thin => &["-flto=auto"],
+        manifest::Lto::Full => &["-flto=auto", "-flto-partition=one"],
+    };
+
+    flags.iter().map(ToString::to_string).collect()
+}
+
+/// Flags defining the `CPPARGO_PKG_*` macros with the project metadata, so
+/// that the project can print its own version without repeating it.
+///
+/// The name, version, authors, description and license are string literals,
+/// empty when unset, and the numbers of the version are integers, defined
+/// only when it's set.
+fn package_defines(manifest: &manifest::Manifest) -> Vec<String> {
+    let metadata = &manifest.metadata;
+    let version = metadata.version.as_deref().unwrap_or_default();
+    let mut defines = vec![
+        ("NAME", c_string_literal(&manifest.name)),
+        ("VERSION", c_string_literal(version)),
+        ("AUTHORS", c_string_literal(&metadata.authors.join(":"))),
+        (
+            "DESCRIPTION",
+            c_string_literal(metadata.description.as_deref().unwrap_or_default()),
+        ),
+        (
+            "LICENSE",
+            c_string_literal(metadata.license.as_deref().unwrap_or_default()),
+        ),
+    ];
+    if metadata.version.is_some() {
+        // Versions are validated to start with dot separated numbers, which
+        // are parsed so that leading zeros don't make them octal.
+        let mut numbers = version
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.');
+        for part in ["VERSION_MAJOR", "VERSION_MINOR", "VERSION_PATCH"] {
+            let number = numbers
+                .next()
+                .and_then(|number| number.parse::<u64>().ok())
+                .unwrap_or_default();
+            defines.push((part, number.to_string()));
+        }
+    }
+
+    defines
+        .into_iter()
+        .map(|(name, value)| format!("-DCPPARGO_PKG_{name}={value}"))
+        .collect()
+}
+
+/// `value` as a C string literal, such as `"foo"`.
+fn c_string_literal(value: &str) -> String {
+    let mut literal = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => {
+                literal.push('\\');
+                literal.push(c);
+            }
+            '\n' => literal.push_str("\\n"),
+            _ => literal.push(c),
+        }
+    }
+    literal.push('"');
+
+    literal
+}
+
+fn lint_flags(lints: &manifest::LintsConfig, deny_warnings: bool) -> Vec<String> {
+    let level_flags: &[&str] = match lints.level {
+        manifest::WarningLevel::None => &["-w"],
+        manifest::WarningLevel::Default => &[],
+        manifest::WarningLevel::All => &["-Wall"],
+        manifest::WarningLevel::Extra => &["-Wall", "-Wextra"],
+        manifest::WarningLevel::Pedantic => &["-Wall", "-Wextra", "-Wpedantic"],
+    };
+
+    let mut flags: Vec<String> = level_flags.iter().map(ToString::to_string).collect();
+    flags.extend(lints.flags.iter().cloned());
+    if deny_warnings {
+        flags.push("-Werror".to_string());
+    }
+
+    flags
+}
+
+/// Ensure the compiler of the `toolchain` supports the sanitizers of the
+/// `options`, and can link with the link-time optimization and linker of the
+/// `profile`, before compiling anything.
+fn ensure_supported(
+    toolchain: &Toolchain,
+    profile: &manifest::ProfileConfig,
+    options: &Options,
+) -> anyhow::Result<()> {
+    if !options.sanitizers.is_empty() {
+        sanitizer::ensure_supported(&toolchain.cpp_compiler, &options.sanitizers)?;
+    }
+    if profile.lto == manifest::Lto::Off && profile.linker.is_none() {
+        return Ok(());
+    }
+
+    let mut probe = Command::new(&toolchain.cpp_compiler);
+    probe
+        .args(&toolchain.link_flags)
+        .args(["-x", "c++", "-", "-o", "/dev/null"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+
+    let mut child = probe
+        .spawn()
+        .with_context(|| format!("Couldn't start compiler: {probe:?}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(b"int main() { return 0; }\n")?;
+    }
+    let output = child.wait_with_output()?;
+
+    anyhow::ensure!(
+        output.status.success(),
+        format!(
+            "Compiler `{}` can't link with {}! Is the linker installed?\n{}",
+            toolchain.cpp_compiler,
+            match (profile.lto, profile.linker) {
+                (manifest::Lto::Off, Some(linker)) => format!("`{}`", linker.name()),
+                (_, Some(linker)) => format!("`{}` and link-time optimization", linker.name()),
+                (_, None) => "link-time optimization".to_string(),
+            },
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        )
+    );
+
+    Ok(())
+}
+
+/// Build the project containing `current_dir`, returning the paths of the
+/// built binaries.
+///
+/// Inside a workspace, the `package` of `options` or every member at the
+/// workspace root is built instead, into the `target/` directory at its root.
+pub fn main(
+    current_dir: &Path,
+    options: &Options,
+    reporter: &Reporter,
+) -> anyhow::Result<Vec<Artifacts>> {
+    sanitizer::validate(&options.sanitizers)?;
+
     let project_root = find_project_root(current_dir).with_context(|| {
         format!(
             "Current directory {} is not inside a `cppargo` project!",
@@ -16,36 +533,589 @@ pub fn main(current_dir: &Path) -> anyhow::Result<()> {
         )
     })?;
 
-    let project_src = project_root.join("src");
+    let Some(workspace) = workspace::find(&project_root)? else {
+        if let Some(package) = &options.package {
+            anyhow::bail!(format!(
+                "Project {} is not inside a workspace to select the member `{package}` from!",
+                project_root.display()
+            ));
+        }
+        return Ok(vec![build_project(&project_root, None, options, reporter)?]);
+    };
 
+    workspace
+        .select(&project_root, options.package.as_deref())?
+        .iter()
+        .map(|member| {
+            build_project(member, Some(&workspace.root), options, reporter)
+                .with_context(|| format!("Failed to build workspace member {}!", member.display()))
+        })
+        .collect()
+}
+
+/// Whether the project at `project_root` is a library, without
+/// `src/main.cpp`, whose objects are only linked into its examples and
+/// benchmarks, or archived, or, for workspace members, into nothing at all.
+///
+/// Fails for a library with nothing to build, unless it's only checked.
+fn is_library(
+    project_root: &Path,
+    workspace_root: Option<&Path>,
+    options: &Options,
+) -> anyhow::Result<bool> {
+    let is_library = !project_root.join(MAIN_SRC_FILE).is_file();
     anyhow::ensure!(
-        project_src.join("main.cpp").is_file(),
+        !is_library
+            || workspace_root.is_some()
+            || options.check
+            || options.archive
+            || !matches!(options.examples, Programs::None)
+            || !matches!(options.benches, Programs::None),
         format!(
             "Missing \"src/main.cpp\" file in {}!",
-            project_src.display()
+            project_root.join("src").display()
         )
     );
 
-    let src_files = find_src_files(&project_src).with_context(|| {
+    Ok(is_library)
+}
+
+/// Build the project at `project_root`, into the `target/` directory of the
+/// `workspace_root` if it's a workspace member.
+fn build_project(
+    project_root: &Path,
+    workspace_root: Option<&Path>,
+    options: &Options,
+    reporter: &Reporter,
+) -> anyhow::Result<Artifacts> {
+    let is_library = is_library(project_root, workspace_root, options)?;
+
+    let project_manifest = project_root.join("Cppargo.toml");
+    let manifest = manifest::read(&project_manifest)?;
+    let platform = options
+        .target
+        .as_deref()
+        .map_or_else(cfg::Platform::host, cfg::Platform::from_triple);
+    let build_config = manifest.build_config(&platform);
+    let mut toolchain = Toolchain::new(&manifest, &build_config, options);
+
+    let mut src_files = collect_src_files(project_root, &build_config).with_context(|| {
         format!(
             "Failed to gather source files from {}!",
-            project_src.display()
+            project_root.display()
         )
     })?;
 
-    let project_manifest = project_root.join("Cppargo.toml");
-    let project_name = get_project_name(&project_manifest)?;
-
-    let project_target = project_root.join("target");
-    ensure_target_dir_exists(&project_target)
+    let target_dir = workspace_root.unwrap_or(project_root).join("target");
+    ensure_target_dir_exists(&target_dir)
         .with_context(|| "Failed to ensure target directory exists for storing built binaries!")?;
 
-    let binary_path = project_target.join(project_name);
-    build_src_files(src_files, &binary_path).with_context(|| "Failed to build source files!")?;
+    ensure_supported(&toolchain, manifest.profiles.get(options.profile), options)?;
+
+    // Paths given to the compiler are relative to the project root, so that
+    // the same command line is produced regardless of where the project is.
+    let output_dir = create_output_dir(project_root, workspace_root, options)?;
+    let binary_path = (!is_library && !options.check).then(|| output_dir.join(&manifest.name));
+    let library_path = (is_library && options.archive && !options.check)
+        .then(|| output_dir.join(format!("lib{}.a", manifest.name)));
+    // Sources of different members may have the same paths.
+    let member_dir = |dir: &str| match workspace_root {
+        Some(_) => output_dir.join(dir).join(&manifest.name),
+        None => output_dir.join(dir),
+    };
+    let (objects_dir, build_dir) = (member_dir("obj"), member_dir("build"));
+
+    if let Some(output) =
+        run_build_script(&build_config, project_root, &build_dir, options, reporter)
+            .with_context(|| "Failed to run build script!")?
+    {
+        toolchain.extend(&output);
+        src_files.extend(output.sources);
+    }
+
+    let src_files = toolchain
+        .use_modules(project_root, &src_files, &member_dir("modules"))
+        .with_context(|| "Failed to scan C++ modules!")?;
+
+    // The header is compiled with the flags of the modules, if any.
+    if let Some(header) = &build_config.precompiled_header {
+        precompile_header(
+            header,
+            project_root,
+            &member_dir("pch"),
+            &mut toolchain,
+            reporter,
+        )
+        .with_context(|| format!("Failed to precompile header {}!", header.display()))?;
+    }
+
+    if options.compile_commands || build_config.compile_commands {
+        write_compile_commands(&src_files, project_root, &objects_dir, &toolchain)
+            .with_context(|| "Failed to write `compile_commands.json`!")?;
+    }
+
+    build_src_files(
+        &src_files,
+        project_root,
+        &objects_dir,
+        binary_path.as_deref(),
+        library_path.as_deref(),
+        &toolchain,
+        reporter,
+    )
+    .with_context(|| "Failed to build source files!")?;
+
+    let lib_object_files: Vec<_> = src_files
+        .iter()
+        .filter(|src_file| *src_file != Path::new(MAIN_SRC_FILE))
+        .map(|src_file| object_file_path(src_file, &objects_dir))
+        .collect();
+    let build_programs = |dir: &str, programs: &Programs| {
+        let mut binaries = BTreeMap::new();
+        for (name, src_file) in find_programs(project_root, dir, programs)? {
+            let binary = output_dir.join(dir).join(&name);
+            build_program(
+                &src_file,
+                &lib_object_files,
+                project_root,
+                &objects_dir,
+                &binary,
+                &toolchain,
+                reporter,
+            )
+            .with_context(|| format!("Failed to build `{name}` from `{dir}/`!"))?;
+            binaries.insert(name, project_root.join(binary));
+        }
+
+        anyhow::Ok(binaries)
+    };
+
+    Ok(Artifacts {
+        project_root: project_root.to_path_buf(),
+        name: manifest.name.clone(),
+        target_dir,
+        objects_dir: project_root.join(&objects_dir),
+        binary: binary_path.map(|binary_path| project_root.join(binary_path)),
+        library: library_path.map(|library_path| project_root.join(library_path)),
+        benches: build_programs("benches", &options.benches)?,
+        examples: build_programs("examples", &options.examples)?,
+        runner: runner(&manifest, options, &platform),
+    })
+}
+
+/// Compile `header` into `pch_dir` with the C++ flags of the `toolchain`,
+/// unless it's up to date, and have every C++ source file include it, except
+/// for module units, which can include it in their global module fragment.
+///
+/// The compiler uses the precompiled `pch_dir/HEADER.gch` in place of the
+/// `pch_dir/HEADER` it's told to include, which itself includes the original
+/// `header`, in case the precompiled header can't be used. It does so through
+/// a path relative to `pch_dir`, and is only written when that changes, so
+/// that it doesn't depend on where the project is, nor rebuild every object.
+///
+/// GCC can't use precompiled headers along with modules, so the header is
+/// only included then.
+fn precompile_header(
+    header: &Path,
+    project_root: &Path,
+    pch_dir: &Path,
+    toolchain: &mut Toolchain,
+    reporter: &Reporter,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        project_root.join(header).is_file(),
+        format!("Header {} not found!", header.display())
+    );
+    let Some(file_name) = header.file_name() else {
+        anyhow::bail!(format!("Header {} has no file name!", header.display()));
+    };
+
+    fs::create_dir_all(project_root.join(pch_dir))
+        .with_context(|| format!("Failed to create directory {}!", pch_dir.display()))?;
+    let include = pch_dir.join(file_name);
+    let contents = format!(
+        "#include \"{}\"\n",
+        relative_path(pch_dir, header, project_root).display()
+    );
+    if fs::read_to_string(project_root.join(&include)).ok() != Some(contents.clone()) {
+        fs::write(project_root.join(&include), contents)
+            .with_context(|| format!("Failed to write {}!", include.display()))?;
+    }
+
+    // GCC compiles headers into header units instead, once modules are on.
+    if toolchain
+        .modules
+        .as_ref()
+        .is_some_and(|interfaces| !interfaces.precompiled)
+    {
+        eprintln!(
+            "[WARN] GCC can't precompile {} along with C++20 modules, so it's only included.",
+            header.display()
+        );
+        toolchain.forced_include = Some(include);
+        return Ok(());
+    }
+
+    let with_extension = |extension: &str| {
+        let mut path = include.clone().into_os_string();
+        path.push(extension);
+        PathBuf::from(path)
+    };
+    let (pch_file, dep_file, command_file) = (
+        with_extension(".gch"),
+        with_extension(".d"),
+        with_extension(".cmd"),
+    );
+
+    let mut compiler = Command::new(&toolchain.cpp_compiler);
+    compiler
+        .args(&toolchain.cpp_flags)
+        .args(&toolchain.compile_flags)
+        .args(["-x", "c++-header"])
+        .arg(header)
+        .arg("-o")
+        .arg(&pch_file)
+        .arg("-MD")
+        .arg("-MF")
+        .arg(&dep_file);
+    let mut compiler = toolchain.launch(compiler);
+    // The header has to be compiled again whenever any flag changes.
+    let command_line = format!("{compiler:?}\n");
+
+    if !is_pch_up_to_date(
+        project_root,
+        &pch_file,
+        &dep_file,
+        &command_file,
+        &command_line,
+    ) {
+        compiler.current_dir(project_root);
+        let compiler_status = run_compiler(&mut compiler, Some(header), project_root, reporter)
+            .with_context(|| format!("Couldn't start compiler: {compiler:?}"))?;
+        anyhow::ensure!(compiler_status.success(), "Compilation failed!");
+
+        fs::write(project_root.join(&command_file), command_line)
+            .with_context(|| format!("Failed to write {}!", command_file.display()))?;
+    }
+
+    toolchain.forced_include = Some(include);
+
     Ok(())
 }
 
-fn find_project_root(dir: &Path) -> anyhow::Result<PathBuf> {
+/// Path leading from the directory `dir` to `path`, both relative to
+/// `project_root`, such as `../../src/pch.hpp` from `target/pch` to
+/// `src/pch.hpp`.
+fn relative_path(dir: &Path, path: &Path, project_root: &Path) -> PathBuf {
+    let resolve = |path: &Path| {
+        let mut resolved = PathBuf::new();
+        for component in project_root.join(path).components() {
+            match component {
+                Component::ParentDir => {
+                    resolved.pop();
+                }
+                Component::CurDir => {}
+                component => resolved.push(component),
+            }
+        }
+        resolved
+    };
+    let (dir, path) = (resolve(dir), resolve(path));
+    let common = dir
+        .components()
+        .zip(path.components())
+        .take_while(|(dir_component, path_component)| dir_component == path_component)
+        .count();
+
+    dir.components()
+        .skip(common)
+        .map(|_| Component::ParentDir)
+        .chain(path.components().skip(common))
+        .collect()
+}
+
+/// Whether `pch_file` was compiled with `command_line`, and after any change
+/// of the headers it depends on, as listed in the `dep_file` written along
+/// with it.
+fn is_pch_up_to_date(
+    project_root: &Path,
+    pch_file: &Path,
+    dep_file: &Path,
+    command_file: &Path,
+    command_line: &str,
+) -> bool {
+    let modified = |path: &Path| fs::metadata(project_root.join(path))?.modified();
+    let Ok(compiled) = modified(pch_file) else {
+        return false;
+    };
+    if fs::read_to_string(project_root.join(command_file))
+        .ok()
+        .as_deref()
+        != Some(command_line)
+    {
+        return false;
+    }
+    let Ok(dependencies) = fs::read_to_string(project_root.join(dep_file)) else {
+        return false;
+    };
+
+    make_dependencies(&dependencies)
+        .iter()
+        .all(|dependency| modified(dependency).is_ok_and(|modified| modified <= compiled))
+}
+
+/// The prerequisites of the rule in the Makefile `dependencies` written by
+/// the compiler with `-MD`.
+fn make_dependencies(dependencies: &str) -> Vec<PathBuf> {
+    let dependencies = dependencies.replace("\\\n", " ");
+    let Some((_, prerequisites)) = dependencies.split_once(": ") else {
+        return Vec::new();
+    };
+
+    let mut paths = Vec::new();
+    let mut escaped_path: Option<String> = None;
+    for part in prerequisites.split_whitespace() {
+        // Spaces inside paths are escaped with a backslash.
+        let path = match escaped_path.take() {
+            Some(start) => format!("{start} {part}"),
+            None => part.to_string(),
+        };
+        match path.strip_suffix('\\') {
+            Some(start) => escaped_path = Some(start.to_string()),
+            None => paths.push(PathBuf::from(path)),
+        }
+    }
+
+    paths
+}
+
+/// Create the directory where a build of the project at `project_root` with
+/// `options` is stored, returning its path relative to `project_root`.
+fn create_output_dir(
+    project_root: &Path,
+    workspace_root: Option<&Path>,
+    options: &Options,
+) -> anyhow::Result<PathBuf> {
+    let output_dir = workspace_path(project_root, workspace_root).join(output_dir(options));
+    if !output_dir.ends_with("target") {
+        fs::create_dir_all(project_root.join(&output_dir)).with_context(|| {
+            format!(
+                "Failed to create output directory {}!",
+                output_dir.display()
+            )
+        })?;
+    }
+
+    Ok(output_dir)
+}
+
+/// Path from a workspace member at `project_root` to its `workspace_root`,
+/// through which it reaches the shared `target/` directory, or nothing for
+/// a standalone project.
+fn workspace_path(project_root: &Path, workspace_root: Option<&Path>) -> PathBuf {
+    let depth = workspace_root
+        .and_then(|workspace_root| project_root.strip_prefix(workspace_root).ok())
+        .map_or(0, |member_dir| member_dir.components().count());
+
+    vec![".."; depth].into_iter().collect()
+}
+
+/// Directory inside `target/` where a build with `options` is stored, so that
+/// different kinds of builds don't clobber each other.
+fn output_dir(options: &Options) -> PathBuf {
+    let mut output_dir = PathBuf::from("target");
+    if let Some(triple) = &options.target {
+        output_dir.push(triple);
+        output_dir.push(match options.profile {
+            manifest::Profile::Dev => "debug",
+            manifest::Profile::Release => "release",
+        });
+    } else if options.profile == manifest::Profile::Release {
+        output_dir.push("release");
+    }
+    if options.coverage {
+        output_dir.push("coverage");
+    }
+    if !options.sanitizers.is_empty() {
+        output_dir.push(sanitizer::dir_name(&options.sanitizers));
+    }
+
+    output_dir
+}
+
+/// Run the build script of the project at `project_root`, if it has one, or
+/// reuse its output from a previous build if nothing it depends on changed
+/// since.
+///
+/// A script source file is compiled for the host into `build_dir`, where the
+/// script's output is saved, and which contains the `out/` directory for the
+/// files it generates.
+///
+/// The generated source files of the output are made relative to
+/// `project_root`, like the project's own.
+fn run_build_script(
+    build_config: &manifest::BuildConfig,
+    project_root: &Path,
+    build_dir: &Path,
+    options: &Options,
+    reporter: &Reporter,
+) -> anyhow::Result<Option<build_script::Output>> {
+    let Some(build_script) = build_config.script.clone().or_else(|| {
+        project_root
+            .join(BUILD_SCRIPT)
+            .is_file()
+            .then(|| manifest::BuildScript::Source(PathBuf::from(BUILD_SCRIPT)))
+    }) else {
+        return Ok(None);
+    };
+
+    let out_dir = project_root.join(build_dir).join("out");
+    fs::create_dir_all(&out_dir)
+        .with_context(|| format!("Failed to create directory {}!", out_dir.display()))?;
+    let output_file = project_root.join(build_dir).join("output");
+
+    let mut script_files = vec![PathBuf::from("Cppargo.toml")];
+    if let manifest::BuildScript::Source(src_file) = &build_script {
+        script_files.push(src_file.clone());
+    }
+    let mut output = match build_script::reuse(project_root, &output_file, &script_files)? {
+        Some(output) => output,
+        None => build_script::run(
+            build_script_command(&build_script, project_root, build_dir, options, reporter)?,
+            &output_file,
+        )?,
+    };
+    for warning in &output.warnings {
+        eprintln!("[WARN] Build script: {warning}");
+    }
+    for src_file in &mut output.sources {
+        if let Ok(relative) = src_file.strip_prefix(project_root) {
+            *src_file = relative.to_path_buf();
+        }
+    }
+
+    Ok(Some(output))
+}
+
+fn build_script_command(
+    build_script: &manifest::BuildScript,
+    project_root: &Path,
+    build_dir: &Path,
+    options: &Options,
+    reporter: &Reporter,
+) -> anyhow::Result<Command> {
+    let out_dir = project_root.join(build_dir).join("out");
+    let mut command = match build_script {
+        manifest::BuildScript::Source(src_file) => {
+            // The script runs on the host, so it's built without any of the
+            // options of the project build.
+            let binary = build_dir.join("build-script");
+            build_program(
+                src_file,
+                &[],
+                project_root,
+                build_dir,
+                &binary,
+                &Toolchain::default(),
+                reporter,
+            )
+            .with_context(|| format!("Failed to build {}!", src_file.display()))?;
+            Command::new(project_root.join(binary))
+        }
+        manifest::BuildScript::Command(command) => {
+            let mut script = Command::new(&command[0]);
+            script.args(&command[1..]);
+            script
+        }
+    };
+    command
+        .current_dir(project_root)
+        .env("CPPARGO_MANIFEST_DIR", project_root)
+        .env("CPPARGO_OUT_DIR", &out_dir)
+        .env(
+            "CPPARGO_PROFILE",
+            match options.profile {
+                manifest::Profile::Dev => "dev",
+                manifest::Profile::Release => "release",
+            },
+        );
+    if let Some(triple) = &options.target {
+        command.env("CPPARGO_TARGET", triple);
+    }
+
+    Ok(command)
+}
+
+fn runner(manifest: &manifest::Manifest, options: &Options, platform: &cfg::Platform) -> Runner {
+    let host = cfg::Platform::host();
+    let foreign_target = options
+        .target
+        .clone()
+        .filter(|_| platform.os != host.os || platform.arch != host.arch);
+
+    Runner {
+        // Past the one given on the command line, a runner for the target,
+        // such as an emulator, is needed more than a general one, such as
+        // `valgrind`, which can't run binaries of a foreign target anyway.
+        command: options.runner.clone().or_else(|| {
+            options
+                .target
+                .as_ref()
+                .and_then(|triple| manifest.triples.get(triple))
+                .and_then(|triple_config| triple_config.runner.clone())
+                .or_else(|| {
+                    manifest
+                        .run
+                        .runner
+                        .clone()
+                        .filter(|_| foreign_target.is_none())
+                })
+        }),
+        foreign_target,
+        env: manifest.run.env.clone(),
+    }
+}
+
+/// The selected source files of `dir`, by the name of their program.
+pub fn find_programs(
+    project_root: &Path,
+    dir: &str,
+    programs: &Programs,
+) -> anyhow::Result<BTreeMap<String, PathBuf>> {
+    let mut found = BTreeMap::new();
+    if matches!(programs, Programs::None) {
+        return Ok(found);
+    }
+
+    let programs_dir = project_root.join(dir);
+    if programs_dir.is_dir() {
+        for entry in fs::read_dir(&programs_dir)
+            .with_context(|| format!("Failed to read {}!", programs_dir.display()))?
+        {
+            let path = entry?.path();
+            if !path.is_file() || Language::of(&path).is_none() {
+                continue;
+            }
+            let (Some(name), Some(file_name)) = (path.file_stem(), path.file_name()) else {
+                continue;
+            };
+            found.insert(
+                name.to_string_lossy().into_owned(),
+                Path::new(dir).join(file_name),
+            );
+        }
+    }
+
+    match programs {
+        Programs::Named(name) => match found.remove_entry(name) {
+            Some(program) => Ok(BTreeMap::from([program])),
+            None => anyhow::bail!(format!("Failed to find `{name}` in `{dir}/`!")),
+        },
+        _ => Ok(found),
+    }
+}
+
+pub fn find_project_root(dir: &Path) -> anyhow::Result<PathBuf> {
     let project_root = match fs::read_dir(dir)?
         .flatten()
         .find(|f| f.file_name() == OsString::from_str("Cppargo.toml").unwrap())
@@ -75,24 +1145,18 @@ fn find_project_root(dir: &Path) -> anyhow::Result<PathBuf> {
     Ok(project_root)
 }
 
-fn find_src_files(project_src: &Path) -> anyhow::Result<HashSet<PathBuf>> {
-    let src_files: HashSet<PathBuf> = fs::read_dir(project_src)
+fn find_src_files(project_src: &Path, strict: bool) -> anyhow::Result<BTreeSet<PathBuf>> {
+    let src_files: BTreeSet<PathBuf> = SourceWalker::new(strict)
+        .find_files(project_src)
         .with_context(|| format!("Couldn't read source directory {}.", &project_src.display()))?
-        .filter_map(|f| Some(f.ok()?.path()))
-        .filter(|f| f.is_dir() || f.extension().is_some_and(|ext| ext == "cpp"))
-        .flat_map(|f| {
-            if f.is_dir() {
-                find_src_files(&f).unwrap_or_default()
-            } else {
-                HashSet::from([f])
-            }
-        })
+        .into_iter()
+        .filter(|f| Language::of(f).is_some())
         .collect();
 
     anyhow::ensure!(
         !src_files.is_empty(),
         format!(
-            "No source `.cpp` files to compile found in \"{}\" directory.",
+            "No source files to compile found in \"{}\" directory.",
             &project_src.display()
         )
     );
@@ -100,19 +1164,204 @@ fn find_src_files(project_src: &Path) -> anyhow::Result<HashSet<PathBuf>> {
     Ok(src_files)
 }
 
-fn get_project_name(project_manifest: &Path) -> anyhow::Result<String> {
-    let manifest = toml_edit::DocumentMut::from_str(&fs::read_to_string(project_manifest)?)
-        .with_context(|| {
-            format!(
-                "Failed to parse project manifest {}!",
-                project_manifest.display()
-            )
-        })?;
-    let Some(project_name) = manifest["project"]["name"].as_str() else {
-        anyhow::bail!("Failed to gather project name!")
+/// Recursive directory search which follows symlinks.
+///
+/// Entries that can't be read, such as broken symlinks or directories without
+/// permissions, are skipped with a warning, or fail the search if `strict`.
+/// Directories are only searched once, so symlink cycles are skipped the same
+/// way.
+struct SourceWalker {
+    strict: bool,
+    visited_dirs: HashSet<PathBuf>,
+}
+
+impl SourceWalker {
+    fn new(strict: bool) -> Self {
+        Self {
+            strict,
+            visited_dirs: HashSet::new(),
+        }
+    }
+
+    /// Find all files inside `dir`. Failing to read `dir` itself is always an
+    /// error.
+    fn find_files(&mut self, dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        self.visit_dir(dir, &mut files)?;
+
+        Ok(files)
+    }
+
+    fn visit_dir(&mut self, dir: &Path, files: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+        let canonical_dir = fs::canonicalize(dir)
+            .with_context(|| format!("Couldn't resolve directory {}.", dir.display()))?;
+        if !self.visited_dirs.insert(canonical_dir) {
+            return self.skip(anyhow::anyhow!(format!(
+                "Directory {} was already searched, possibly through a symlink cycle.",
+                dir.display()
+            )));
+        }
+
+        let entries = fs::read_dir(dir)
+            .with_context(|| format!("Couldn't read directory {}.", dir.display()))?;
+        for entry in entries {
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(err) => {
+                    self.skip(anyhow::Error::new(err).context(format!(
+                        "Couldn't read entry of directory {}.",
+                        dir.display()
+                    )))?;
+                    continue;
+                }
+            };
+
+            // `fs::metadata` follows symlinks, so it fails for broken ones.
+            let metadata = match fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(err) => {
+                    let is_symlink = path.is_symlink();
+                    self.skip(anyhow::Error::new(err).context(if is_symlink {
+                        format!("Broken symlink {}.", path.display())
+                    } else {
+                        format!("Couldn't read {}.", path.display())
+                    }))?;
+                    continue;
+                }
+            };
+
+            if metadata.is_dir() {
+                if let Err(err) = self.visit_dir(&path, files) {
+                    self.skip(err)?;
+                }
+            } else {
+                files.push(path);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Warn about an entry that is being skipped, or fail if `strict`.
+    fn skip(&self, err: anyhow::Error) -> anyhow::Result<()> {
+        if self.strict {
+            return Err(err);
+        }
+
+        eprintln!("[WARN] Skipping unreadable source entry: {err:#}");
+
+        Ok(())
+    }
+}
+
+/// Gather the source files to compile, either from the `[build] sources`
+/// globs, or every source file inside `PROJECT_ROOT/src`, leaving out those
+/// matching the `[build] exclude` globs.
+///
+/// The returned paths are relative to `project_root`, and sorted so that the
+/// compiler is always invoked in the same order.
+fn collect_src_files(
+    project_root: &Path,
+    build_config: &manifest::BuildConfig,
+) -> anyhow::Result<BTreeSet<PathBuf>> {
+    let src_files = match &build_config.sources {
+        Some(sources) => find_glob_src_files(project_root, sources, build_config.strict_sources)?,
+        None => find_src_files(&project_root.join("src"), build_config.strict_sources)?,
     };
 
-    Ok(String::from_str(project_name)?)
+    let mut src_files = src_files
+        .into_iter()
+        .map(|f| match f.strip_prefix(project_root) {
+            Ok(relative) => Ok(relative.to_path_buf()),
+            Err(_) => anyhow::bail!(format!(
+                "Source file {} is not inside project root {}!",
+                f.display(),
+                project_root.display()
+            )),
+        })
+        .collect::<anyhow::Result<BTreeSet<PathBuf>>>()?;
+
+    let exclude = build_glob_set(&build_config.exclude)?;
+    src_files.retain(|f| !exclude.is_match(f));
+
+    anyhow::ensure!(
+        !src_files.is_empty(),
+        "All source files were excluded from compilation!"
+    );
+
+    Ok(src_files)
+}
+
+fn find_glob_src_files(
+    project_root: &Path,
+    sources: &[String],
+    strict: bool,
+) -> anyhow::Result<BTreeSet<PathBuf>> {
+    let glob_set = build_glob_set(sources)?;
+
+    // Globs sharing their base directory, such as `src/**/*.cpp` and
+    // `src/**/*.c`, or whose base is inside the one of another, such as
+    // `src/**` and `src/foo/*.cpp`, have it searched only once, since the
+    // walker otherwise takes the second search for a symlink cycle.
+    let mut bases: Vec<PathBuf> = Vec::new();
+    for base in sources
+        .iter()
+        .map(|glob| glob_base(glob))
+        .collect::<BTreeSet<_>>()
+    {
+        if !bases.iter().any(|searched| base.starts_with(searched)) {
+            bases.push(base);
+        }
+    }
+
+    let mut src_files = BTreeSet::new();
+    let mut walker = SourceWalker::new(strict);
+    for base in bases {
+        let base = project_root.join(base);
+        let candidates = if base.is_dir() {
+            walker.find_files(&base)?
+        } else if base.is_file() {
+            vec![base]
+        } else {
+            continue;
+        };
+
+        src_files.extend(candidates.into_iter().filter(|f| {
+            Language::of(f).is_some()
+                && glob_set.is_match(f.strip_prefix(project_root).unwrap_or(f))
+        }));
+    }
+
+    anyhow::ensure!(
+        !src_files.is_empty(),
+        format!("No source files to compile match the globs {sources:?}.")
+    );
+
+    Ok(src_files)
+}
+
+pub fn build_glob_set(globs: &[String]) -> anyhow::Result<GlobSet> {
+    let mut glob_set = GlobSetBuilder::new();
+    for glob in globs {
+        glob_set.add(
+            GlobBuilder::new(glob)
+                .literal_separator(true)
+                .build()
+                .with_context(|| format!("Invalid glob `{glob}`!"))?,
+        );
+    }
+
+    glob_set
+        .build()
+        .with_context(|| format!("Invalid globs {globs:?}!"))
+}
+
+/// The leading components of `glob` without any glob metacharacters, which is
+/// the directory (or file) that needs to be searched for matches.
+pub fn glob_base(glob: &str) -> PathBuf {
+    glob.split('/')
+        .take_while(|component| !component.contains(['*', '?', '[', '{']))
+        .collect()
 }
 
 fn ensure_target_dir_exists(project_target: &Path) -> anyhow::Result<()> {
@@ -128,37 +1377,471 @@ fn ensure_target_dir_exists(project_target: &Path) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn build_src_files(src_files: HashSet<PathBuf>, binary_path: &Path) -> anyhow::Result<()> {
-    let output_args = [
-        "-o",
-        match binary_path.to_str() {
-            Some(s) => s,
-            None => anyhow::bail!(format!(
-                "Failed to parse binary path to string: {}",
-                binary_path.display()
-            )),
-        },
-    ];
+/// Compile every source file into an object file inside `objects_dir`, and
+/// link them all into `binary_path`, unless the project is a library, whose
+/// objects are archived into `library_path` instead, if given.
+///
+/// All paths are relative to `project_root`, where the compiler is run.
+fn build_src_files(
+    src_files: &[PathBuf],
+    project_root: &Path,
+    objects_dir: &Path,
+    binary_path: Option<&Path>,
+    library_path: Option<&Path>,
+    toolchain: &Toolchain,
+    reporter: &Reporter,
+) -> anyhow::Result<()> {
+    let mut object_files = Vec::with_capacity(src_files.len());
+    for src_file in src_files {
+        let object_file = object_file_path(src_file, objects_dir);
+        compile_src_file(src_file, &object_file, project_root, toolchain, reporter)
+            .with_context(|| format!("Failed to compile {}!", src_file.display()))?;
+        object_files.push(object_file);
+    }
+
+    if let Some(library_path) = library_path {
+        archive_library(
+            &object_files,
+            library_path,
+            project_root,
+            toolchain,
+            reporter,
+        )
+        .with_context(|| format!("Failed to archive {}!", library_path.display()))?;
+    }
+
+    match binary_path {
+        Some(binary_path) => link_binary(
+            &object_files,
+            binary_path,
+            project_root,
+            toolchain,
+            reporter,
+        ),
+        None => Ok(()),
+    }
+}
+
+/// Compile the single `src_file` of a program, such as a benchmark, and link
+/// it along with the already compiled `lib_object_files` into `binary_path`.
+fn build_program(
+    src_file: &Path,
+    lib_object_files: &[PathBuf],
+    project_root: &Path,
+    objects_dir: &Path,
+    binary_path: &Path,
+    toolchain: &Toolchain,
+    reporter: &Reporter,
+) -> anyhow::Result<()> {
+    let object_file = object_file_path(src_file, objects_dir);
+    compile_src_file(src_file, &object_file, project_root, toolchain, reporter)
+        .with_context(|| format!("Failed to compile {}!", src_file.display()))?;
+
+    let mut object_files = vec![object_file];
+    object_files.extend(lib_object_files.iter().cloned());
+    if let Some(binary_dir) = binary_path.parent() {
+        fs::create_dir_all(project_root.join(binary_dir))
+            .with_context(|| format!("Failed to create directory {}!", binary_dir.display()))?;
+    }
+
+    link_binary(
+        &object_files,
+        binary_path,
+        project_root,
+        toolchain,
+        reporter,
+    )
+}
+
+fn link_binary(
+    object_files: &[PathBuf],
+    binary_path: &Path,
+    project_root: &Path,
+    toolchain: &Toolchain,
+    reporter: &Reporter,
+) -> anyhow::Result<()> {
+    let mut linker = link_command(object_files, binary_path, toolchain);
+    linker.current_dir(project_root);
+    let linker_status = run_compiler(&mut linker, None, project_root, reporter)
+        .with_context(|| format!("Couldn't start linker: {linker:?}"))?;
+
+    anyhow::ensure!(linker_status.success(), "Linking failed!");
+
+    let debug_file = strip_binary(binary_path, project_root, toolchain)
+        .with_context(|| format!("Failed to strip {}!", binary_path.display()))?;
+
+    reporter.artifact(&project_root.join(binary_path));
+    if let Some(debug_file) = debug_file {
+        reporter.artifact(&project_root.join(debug_file));
+    }
 
-    let mut compiler = Command::new("g++");
-    compiler.args(output_args).args(src_files);
-    println!("Running compiler...\n{:?}", &compiler);
-    let compiler_status = compiler
-        .status()
+    Ok(())
+}
+
+/// Archive the `object_files` of a library into the static library
+/// `library_path`, replacing any previous archive so that it doesn't keep the
+/// objects of removed sources.
+fn archive_library(
+    object_files: &[PathBuf],
+    library_path: &Path,
+    project_root: &Path,
+    toolchain: &Toolchain,
+    reporter: &Reporter,
+) -> anyhow::Result<()> {
+    match fs::remove_file(project_root.join(library_path)) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("Failed to remove {}!", library_path.display()))
+        }
+    }
+
+    let mut ar = Command::new(&toolchain.ar);
+    ar.arg("rcs")
+        .arg(library_path)
+        .args(object_files)
+        .current_dir(project_root);
+    let output = ar
+        .output()
+        .with_context(|| format!("Couldn't start `{}`: {ar:?}", toolchain.ar))?;
+    anyhow::ensure!(
+        output.status.success(),
+        format!(
+            "`{}` failed!\n{}",
+            toolchain.ar,
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        )
+    );
+
+    reporter.artifact(&project_root.join(library_path));
+
+    Ok(())
+}
+
+/// Move the debug information of `binary_path` into `BINARY.debug`, which the
+/// binary then links to, and strip the binary, as configured by the profile
+/// of the `toolchain`.
+///
+/// Returns the file with the split debug information, if any.
+fn strip_binary(
+    binary_path: &Path,
+    project_root: &Path,
+    toolchain: &Toolchain,
+) -> anyhow::Result<Option<PathBuf>> {
+    let debug_file = toolchain.split_debuginfo.then(|| {
+        let mut debug_file = binary_path.as_os_str().to_os_string();
+        debug_file.push(".debug");
+        PathBuf::from(debug_file)
+    });
+    // The debug information of a binary is stripped once it's split off.
+    let strip_flag = match (toolchain.strip, &debug_file) {
+        (manifest::Strip::Symbols, _) => Some("--strip-all"),
+        (manifest::Strip::Debuginfo, _) | (manifest::Strip::None, Some(_)) => Some("--strip-debug"),
+        (manifest::Strip::None, None) => None,
+    };
+
+    let binary: OsString = binary_path.into();
+    let mut steps: Vec<Vec<OsString>> = Vec::new();
+    if let Some(debug_file) = &debug_file {
+        steps.push(vec![
+            "--only-keep-debug".into(),
+            binary.clone(),
+            debug_file.into(),
+        ]);
+    }
+    if let Some(strip_flag) = strip_flag {
+        steps.push(vec![strip_flag.into(), binary.clone()]);
+    }
+    if let Some(debug_file) = &debug_file {
+        let mut debuglink = OsString::from("--add-gnu-debuglink=");
+        debuglink.push(debug_file);
+        steps.push(vec![debuglink, binary]);
+    }
+
+    for args in steps {
+        let mut objcopy = Command::new(&toolchain.objcopy);
+        objcopy.args(args).current_dir(project_root);
+        let output = objcopy
+            .output()
+            .with_context(|| format!("Couldn't start `{}`: {objcopy:?}", toolchain.objcopy))?;
+        anyhow::ensure!(
+            output.status.success(),
+            format!(
+                "`{}` failed!\n{}",
+                toolchain.objcopy,
+                String::from_utf8_lossy(&output.stderr).trim_end()
+            )
+        );
+    }
+
+    Ok(debug_file)
+}
+
+fn object_file_path(src_file: &Path, objects_dir: &Path) -> PathBuf {
+    // Sources outside the project, such as generated ones in the `target/`
+    // directory of a workspace, are kept inside `objects_dir` too.
+    let src_file: PathBuf = src_file
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect();
+    // Keep the source extension so that `foo.c` and `foo.cpp` don't collide.
+    let mut object_file = objects_dir.join(src_file).into_os_string();
+    object_file.push(".o");
+
+    PathBuf::from(object_file)
+}
+
+fn compile_src_file(
+    src_file: &Path,
+    object_file: &Path,
+    project_root: &Path,
+    toolchain: &Toolchain,
+    reporter: &Reporter,
+) -> anyhow::Result<()> {
+    if let Some(object_dir) = object_file.parent() {
+        let object_dir = project_root.join(object_dir);
+        fs::create_dir_all(&object_dir).with_context(|| {
+            format!(
+                "Failed to create object directory {}!",
+                object_dir.display()
+            )
+        })?;
+    }
+
+    let mut commands = Vec::with_capacity(2);
+    if let Some(interface) = toolchain.precompiled_interface(src_file) {
+        commands.push(precompile_command(src_file, &interface, toolchain));
+    }
+    commands.push(compile_command(src_file, object_file, toolchain)?);
+
+    for compiler in commands {
+        let mut compiler = toolchain.launch(compiler);
+        compiler.current_dir(project_root);
+        if let Some(cache) = &toolchain.object_cache {
+            compile_cached(
+                cache,
+                &mut compiler,
+                src_file,
+                object_file,
+                project_root,
+                reporter,
+            )?;
+        } else {
+            let compiler_status =
+                run_compiler(&mut compiler, Some(src_file), project_root, reporter)
+                    .with_context(|| format!("Couldn't start compiler: {compiler:?}"))?;
+
+            anyhow::ensure!(compiler_status.success(), "Compilation failed!");
+        }
+    }
+
+    Ok(())
+}
+
+/// Copy the object file of `src_file` from the object `cache` if it was
+/// compiled the same way before, or otherwise compile it with the `compiler`
+/// and cache it.
+fn compile_cached(
+    cache: &cache::ObjectCache,
+    compiler: &mut Command,
+    src_file: &Path,
+    object_file: &Path,
+    project_root: &Path,
+    reporter: &Reporter,
+) -> anyhow::Result<()> {
+    let object_path = project_root.join(object_file);
+    let key = cache.key(compiler)?;
+    if let Some(printed) = key.as_ref().and_then(|key| cache.get(key, &object_path)) {
+        reporter.status(&format!("Reusing cached {}", object_file.display()));
+        report_compiler_output(&printed, Some(src_file), project_root, reporter);
+        return Ok(());
+    }
+
+    reporter.compiler_invocation(compiler, Some(src_file));
+    let output = compiler
+        .output()
         .with_context(|| format!("Couldn't start compiler: {compiler:?}"))?;
+    let mut printed = String::from_utf8_lossy(&output.stdout).into_owned();
+    printed.push_str(&String::from_utf8_lossy(&output.stderr));
+    report_compiler_output(&printed, Some(src_file), project_root, reporter);
 
-    anyhow::ensure!(compiler_status.success(), "Compilation failed!");
+    anyhow::ensure!(output.status.success(), "Compilation failed!");
+
+    if let Some(key) = key {
+        if let Err(err) = cache.put(&key, &object_path, &printed) {
+            eprintln!("[WARN] Failed to cache {}: {err:#}", object_file.display());
+        }
+    }
 
     Ok(())
 }
 
+/// Run a compiler (or linker, if `src_file` is `None`) `command`, reporting
+/// it and the diagnostics it prints, with paths relative to `project_root`,
+/// through `reporter`.
+fn run_compiler(
+    command: &mut Command,
+    src_file: Option<&Path>,
+    project_root: &Path,
+    reporter: &Reporter,
+) -> anyhow::Result<ExitStatus> {
+    reporter.compiler_invocation(command, src_file);
+
+    let output = command.output()?;
+    let mut printed = String::from_utf8_lossy(&output.stdout).into_owned();
+    printed.push_str(&String::from_utf8_lossy(&output.stderr));
+    report_compiler_output(&printed, src_file, project_root, reporter);
+
+    Ok(output.status)
+}
+
+/// Report the diagnostics a compiler `printed`, with paths relative to
+/// `project_root`, through `reporter`.
+fn report_compiler_output(
+    printed: &str,
+    src_file: Option<&Path>,
+    project_root: &Path,
+    reporter: &Reporter,
+) {
+    let mut compiler_output = diagnostic::parse(printed);
+    for diagnostic in compiler_output.diagnostics_mut() {
+        diagnostic.relativize(project_root);
+    }
+    reporter.compiler_output(src_file, compiler_output);
+}
+
+fn compile_command(
+    src_file: &Path,
+    object_file: &Path,
+    toolchain: &Toolchain,
+) -> anyhow::Result<Command> {
+    let Some(language) = Language::of(src_file) else {
+        anyhow::bail!(format!(
+            "Unknown source language of {}!",
+            src_file.display()
+        ));
+    };
+
+    let mut compiler = Command::new(toolchain.compiler(language));
+    compiler.args(toolchain.flags(language));
+    // Nothing may come before the module declaration of a module unit.
+    if let Some(header) = toolchain
+        .forced_include
+        .as_ref()
+        .filter(|_| language == Language::Cpp && !toolchain.module_units.contains(src_file))
+    {
+        compiler.arg("-Winvalid-pch").arg("-include").arg(header);
+    }
+    compiler.args(&toolchain.compile_flags);
+    // Print fix-its in a parseable format, so they can be reported.
+    compiler.arg("-fdiagnostics-parseable-fixits");
+    match toolchain.precompiled_interface(src_file) {
+        // Clang compiles the object file of a module interface unit from its
+        // precompiled interface.
+        Some(interface) => compiler.arg("-c").arg(interface),
+        None if modules::is_interface_file(src_file) => {
+            compiler.args(["-x", "c++", "-c"]).arg(src_file)
+        }
+        None => compiler.arg("-c").arg(src_file),
+    };
+    compiler.arg("-o").arg(object_file);
+
+    Ok(compiler)
+}
+
+/// Command precompiling the module interface unit `src_file` into
+/// `interface`, with Clang.
+fn precompile_command(src_file: &Path, interface: &Path, toolchain: &Toolchain) -> Command {
+    let mut compiler = Command::new(&toolchain.cpp_compiler);
+    compiler.args(&toolchain.cpp_flags);
+    compiler.args(&toolchain.compile_flags);
+    compiler.arg("-fdiagnostics-parseable-fixits");
+    compiler
+        .args(["-x", "c++-module", "--precompile"])
+        .arg(src_file)
+        .arg("-o")
+        .arg(interface);
+
+    compiler
+}
+
+/// Write a `compile_commands.json` compilation database at the project root,
+/// with the exact command used to compile each source file, for tools such as
+/// `clangd` or `clang-tidy`.
+fn write_compile_commands(
+    src_files: &[PathBuf],
+    project_root: &Path,
+    objects_dir: &Path,
+    toolchain: &Toolchain,
+) -> anyhow::Result<()> {
+    let compile_commands = compile_commands(src_files, project_root, objects_dir, toolchain)?;
+
+    let compile_commands_path = project_root.join("compile_commands.json");
+    fs::write(
+        &compile_commands_path,
+        serde_json::to_string_pretty(&compile_commands)? + "\n",
+    )
+    .with_context(|| {
+        format!(
+            "Failed to write compilation database at {}!",
+            compile_commands_path.display()
+        )
+    })?;
+
+    Ok(())
+}
+
+fn compile_commands(
+    src_files: &[PathBuf],
+    project_root: &Path,
+    objects_dir: &Path,
+    toolchain: &Toolchain,
+) -> anyhow::Result<serde_json::Value> {
+    let directory = project_root.to_string_lossy();
+
+    let entries = src_files
+        .iter()
+        .map(|src_file| {
+            let object_file = object_file_path(src_file, objects_dir);
+            // Clang parses module interface units when precompiling them.
+            let compiler = match toolchain.precompiled_interface(src_file) {
+                Some(interface) => precompile_command(src_file, &interface, toolchain),
+                None => compile_command(src_file, &object_file, toolchain)?,
+            };
+            let arguments: Vec<_> = std::iter::once(compiler.get_program())
+                .chain(compiler.get_args())
+                .map(|arg| arg.to_string_lossy())
+                .collect();
+
+            Ok(serde_json::json!({
+                "directory": directory,
+                "file": src_file.to_string_lossy(),
+                "arguments": arguments,
+                "output": object_file.to_string_lossy(),
+            }))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(serde_json::Value::Array(entries))
+}
+
+fn link_command(object_files: &[PathBuf], binary_path: &Path, toolchain: &Toolchain) -> Command {
+    let mut linker = Command::new(&toolchain.cpp_compiler);
+    linker.args(&toolchain.link_flags);
+    linker.arg("-o").arg(binary_path).args(object_files);
+    linker.args(&toolchain.link_libs);
+
+    linker
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::message::MessageFormat;
     use assert_fs::prelude::*;
 
     const PROJECT_MANIFEST: &str = "[project]\nname = \"foo\"\n";
-    const PROJEJCT_MANIFEST_WITH_NO_NAME: &str = "[project]\nname =\n";
 
     const MAIN_FILE_WITH_INCLUDE_MODULE: &str = concat!(
         "#include <iostream>\n",
@@ -180,6 +1863,21 @@ mod tests {
         "}\n"
     );
 
+    const MAIN_FILE_CALLING_C: &str = concat!(
+        "extern \"C\" int c_answer();\n",
+        "\n",
+        "int main() {\n",
+        "    return c_answer() == 42 ? 0 : 1;\n",
+        "}\n"
+    );
+
+    const C_MODULE_FILE: &str = concat!(
+        "int c_answer(void) {\n",
+        "    for (int i = 0; i < 42; i++) {}\n",
+        "    return 42;\n",
+        "}\n"
+    );
+
     #[cfg(test)]
     mod find_project_root {
         use super::*;
@@ -243,8 +1941,8 @@ mod tests {
         use super::*;
 
         fn ensure_found_expected_files(
-            found: &HashSet<PathBuf>,
-            expected: &HashSet<PathBuf>,
+            found: &BTreeSet<PathBuf>,
+            expected: &BTreeSet<PathBuf>,
         ) -> anyhow::Result<()> {
             anyhow::ensure!(
                 found == expected,
@@ -258,15 +1956,132 @@ mod tests {
         }
 
         #[test]
-        fn find_main() -> anyhow::Result<()> {
+        fn find_main() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_src = tmp_dir.child("src");
+
+            let main_file = project_src.child("main.cpp");
+            main_file.touch()?;
+
+            let found_src_files = find_src_files(&project_src, false)?;
+            let expected_src_files = BTreeSet::from([main_file].map(|f| f.to_path_buf()));
+
+            ensure_found_expected_files(&found_src_files, &expected_src_files)?;
+
+            Ok(())
+        }
+
+        #[test]
+        fn find_only_source_files() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_src = tmp_dir.child("src");
+
+            let main_file = project_src.child("main.cpp");
+            main_file.touch()?;
+
+            let header_file = project_src.child("header.hpp");
+            header_file.touch()?;
+
+            let text_file = project_src.child("text.txt");
+            text_file.touch()?;
+
+            let c_file = project_src.child("c_file.c");
+            c_file.touch()?;
+
+            let c_header_file = project_src.child("c_header.h");
+            c_header_file.touch()?;
+
+            let binary_file = project_src.child("binary");
+            binary_file.touch()?;
+
+            let found_src_files = find_src_files(&project_src, false)?;
+            let expected_src_files = BTreeSet::from([main_file, c_file].map(|f| f.to_path_buf()));
+
+            ensure_found_expected_files(&found_src_files, &expected_src_files)?;
+
+            Ok(())
+        }
+
+        #[test]
+        fn find_all_cpp_extensions() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_src = tmp_dir.child("src");
+
+            let main_file = project_src.child("main.cpp");
+            main_file.touch()?;
+
+            let cc_file = project_src.child("cc_file.cc");
+            cc_file.touch()?;
+
+            let cxx_file = project_src.child("cxx_file.cxx");
+            cxx_file.touch()?;
+
+            let cplusplus_file = project_src.child("cplusplus_file.c++");
+            cplusplus_file.touch()?;
+
+            let found_src_files = find_src_files(&project_src, false)?;
+            let expected_src_files = BTreeSet::from(
+                [main_file, cc_file, cxx_file, cplusplus_file].map(|f| f.to_path_buf()),
+            );
+
+            ensure_found_expected_files(&found_src_files, &expected_src_files)?;
+
+            Ok(())
+        }
+
+        #[test]
+        fn find_same_dir_files() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_src = tmp_dir.child("src");
+
+            let main_file = project_src.child("main.cpp");
+            main_file.touch()?;
+
+            let other_file = project_src.child("other.cpp");
+            other_file.touch()?;
+
+            let found_src_files = find_src_files(&project_src, false)?;
+            let expected_src_files =
+                BTreeSet::from([main_file, other_file].map(|f| f.to_path_buf()));
+
+            ensure_found_expected_files(&found_src_files, &expected_src_files)?;
+
+            Ok(())
+        }
+
+        #[test]
+        fn find_with_empty_directories() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_src = tmp_dir.child("src");
+
+            let main_file = project_src.child("main.cpp");
+            main_file.touch()?;
+
+            let empty_dir = project_src.child("empty");
+            empty_dir.create_dir_all()?;
+
+            let found_src_files = find_src_files(&project_src, false)?;
+            let expected_src_files = BTreeSet::from([main_file].map(|f| f.to_path_buf()));
+
+            ensure_found_expected_files(&found_src_files, &expected_src_files)?;
+
+            Ok(())
+        }
+
+        #[test]
+        fn find_within_nested_directories() -> anyhow::Result<()> {
             let tmp_dir = assert_fs::TempDir::new()?;
             let project_src = tmp_dir.child("src");
 
             let main_file = project_src.child("main.cpp");
             main_file.touch()?;
 
-            let found_src_files = find_src_files(&project_src)?;
-            let expected_src_files = HashSet::from([main_file].map(|f| f.to_path_buf()));
+            let nested_file = project_src.child("nested").child("nested.cpp");
+            nested_file.touch()?;
+
+            let found_src_files = find_src_files(&project_src, false)?;
+            let expected_src_files =
+                BTreeSet::from([main_file, nested_file].map(|f| f.to_path_buf()));
 
             ensure_found_expected_files(&found_src_files, &expected_src_files)?;
 
@@ -274,30 +2089,44 @@ mod tests {
         }
 
         #[test]
-        fn find_only_cpp_files() -> anyhow::Result<()> {
+        fn find_within_doubly_nested_directories() -> anyhow::Result<()> {
             let tmp_dir = assert_fs::TempDir::new()?;
             let project_src = tmp_dir.child("src");
 
             let main_file = project_src.child("main.cpp");
             main_file.touch()?;
 
-            let header_file = project_src.child("header.hpp");
-            header_file.touch()?;
+            let doubly_nested_file = project_src
+                .child("doubly")
+                .child("nested")
+                .child("doubly_nested.cpp");
+            doubly_nested_file.touch()?;
 
-            let text_file = project_src.child("text.txt");
-            text_file.touch()?;
+            let found_src_files = find_src_files(&project_src, false)?;
+            let expected_src_files =
+                BTreeSet::from([main_file, doubly_nested_file].map(|f| f.to_path_buf()));
 
-            let c_file = project_src.child("c_file.c");
-            c_file.touch()?;
+            ensure_found_expected_files(&found_src_files, &expected_src_files)?;
 
-            let c_header_file = project_src.child("c_header.h");
-            c_header_file.touch()?;
+            Ok(())
+        }
 
-            let binary_file = project_src.child("binary");
-            binary_file.touch()?;
+        #[test]
+        fn find_beside_header_only_directories() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_src = tmp_dir.child("src");
 
-            let found_src_files = find_src_files(&project_src)?;
-            let expected_src_files = HashSet::from([main_file].map(|f| f.to_path_buf()));
+            let main_file = project_src.child("main.cpp");
+            main_file.touch()?;
+
+            project_src.child("headers").child("header.hpp").touch()?;
+
+            let nested_file = project_src.child("nested").child("nested.cpp");
+            nested_file.touch()?;
+
+            let found_src_files = find_src_files(&project_src, true)?;
+            let expected_src_files =
+                BTreeSet::from([main_file, nested_file].map(|f| f.to_path_buf()));
 
             ensure_found_expected_files(&found_src_files, &expected_src_files)?;
 
@@ -305,19 +2134,22 @@ mod tests {
         }
 
         #[test]
-        fn find_same_dir_files() -> anyhow::Result<()> {
+        fn follow_symlinked_directories() -> anyhow::Result<()> {
             let tmp_dir = assert_fs::TempDir::new()?;
             let project_src = tmp_dir.child("src");
 
             let main_file = project_src.child("main.cpp");
             main_file.touch()?;
 
-            let other_file = project_src.child("other.cpp");
-            other_file.touch()?;
+            let external_dir = tmp_dir.child("external");
+            external_dir.child("external.cpp").touch()?;
+
+            let linked_dir = project_src.child("linked");
+            linked_dir.symlink_to_dir(external_dir.path())?;
 
-            let found_src_files = find_src_files(&project_src)?;
+            let found_src_files = find_src_files(&project_src, true)?;
             let expected_src_files =
-                HashSet::from([main_file, other_file].map(|f| f.to_path_buf()));
+                BTreeSet::from([main_file.to_path_buf(), linked_dir.join("external.cpp")]);
 
             ensure_found_expected_files(&found_src_files, &expected_src_files)?;
 
@@ -325,85 +2157,121 @@ mod tests {
         }
 
         #[test]
-        fn find_with_empty_directories() -> anyhow::Result<()> {
+        fn skip_symlink_cycles() -> anyhow::Result<()> {
             let tmp_dir = assert_fs::TempDir::new()?;
             let project_src = tmp_dir.child("src");
 
             let main_file = project_src.child("main.cpp");
             main_file.touch()?;
 
-            let empty_dir = project_src.child("empty");
-            empty_dir.create_dir_all()?;
+            let nested_dir = project_src.child("nested");
+            nested_dir.create_dir_all()?;
+            nested_dir
+                .child("cycle")
+                .symlink_to_dir(project_src.path())?;
 
-            let found_src_files = find_src_files(&project_src)?;
-            let expected_src_files = HashSet::from([main_file].map(|f| f.to_path_buf()));
+            let found_src_files = find_src_files(&project_src, false)?;
+            let expected_src_files = BTreeSet::from([main_file.to_path_buf()]);
 
             ensure_found_expected_files(&found_src_files, &expected_src_files)?;
 
+            anyhow::ensure!(
+                find_src_files(&project_src, true).is_err(),
+                "Didn't fail on symlink cycle with strict search!"
+            );
+
             Ok(())
         }
 
         #[test]
-        fn find_within_nested_directories() -> anyhow::Result<()> {
+        fn skip_broken_symlinks() -> anyhow::Result<()> {
             let tmp_dir = assert_fs::TempDir::new()?;
             let project_src = tmp_dir.child("src");
 
             let main_file = project_src.child("main.cpp");
             main_file.touch()?;
 
-            let nested_file = project_src.child("nested").child("nested.cpp");
-            nested_file.touch()?;
+            project_src
+                .child("broken.cpp")
+                .symlink_to_file(tmp_dir.child("missing.cpp").path())?;
 
-            let found_src_files = find_src_files(&project_src)?;
-            let expected_src_files =
-                HashSet::from([main_file, nested_file].map(|f| f.to_path_buf()));
+            let found_src_files = find_src_files(&project_src, false)?;
+            let expected_src_files = BTreeSet::from([main_file.to_path_buf()]);
 
             ensure_found_expected_files(&found_src_files, &expected_src_files)?;
 
-            Ok(())
+            match find_src_files(&project_src, true) {
+                Err(err)
+                    if err
+                        .chain()
+                        .any(|e| e.to_string().starts_with("Broken symlink")) =>
+                {
+                    Ok(())
+                }
+                Err(err) => anyhow::bail!(format!("Got a non-expected error: \"{err:#}\"!")),
+                Ok(found) => anyhow::bail!(format!(
+                    "Didn't fail on broken symlink with strict search! Found: {found:?}"
+                )),
+            }
         }
 
+        #[cfg(unix)]
         #[test]
-        fn find_within_doubly_nested_directories() -> anyhow::Result<()> {
+        fn skip_unreadable_directories() -> anyhow::Result<()> {
+            use std::os::unix::fs::PermissionsExt;
+
             let tmp_dir = assert_fs::TempDir::new()?;
             let project_src = tmp_dir.child("src");
 
             let main_file = project_src.child("main.cpp");
             main_file.touch()?;
 
-            let doubly_nested_file = project_src
-                .child("doubly")
-                .child("nested")
-                .child("doubly_nested.cpp");
-            doubly_nested_file.touch()?;
+            let unreadable_dir = project_src.child("unreadable");
+            unreadable_dir.child("hidden.cpp").touch()?;
+            fs::set_permissions(&unreadable_dir, fs::Permissions::from_mode(0o000))?;
 
-            let found_src_files = find_src_files(&project_src)?;
-            let expected_src_files =
-                HashSet::from([main_file, doubly_nested_file].map(|f| f.to_path_buf()));
+            // Privileged users can read the directory regardless.
+            if fs::read_dir(&unreadable_dir).is_ok() {
+                return Ok(());
+            }
 
-            ensure_found_expected_files(&found_src_files, &expected_src_files)?;
+            let found_src_files = find_src_files(&project_src, false);
+            let strict_found_src_files = find_src_files(&project_src, true);
+            fs::set_permissions(&unreadable_dir, fs::Permissions::from_mode(0o755))?;
+
+            let expected_src_files = BTreeSet::from([main_file.to_path_buf()]);
+            ensure_found_expected_files(&found_src_files?, &expected_src_files)?;
+
+            anyhow::ensure!(
+                strict_found_src_files.is_err(),
+                "Didn't fail on unreadable directory with strict search!"
+            );
 
             Ok(())
         }
     }
 
     #[cfg(test)]
-    mod get_project_name {
+    mod collect_src_files {
         use super::*;
 
-        #[test]
-        fn succeed() -> anyhow::Result<()> {
-            let tmp_dir = assert_fs::TempDir::new()?;
-            let project_manifest = tmp_dir.child("Cppargo.toml");
-            project_manifest.write_str(PROJECT_MANIFEST)?;
-
-            let project_name = get_project_name(project_manifest.path())?;
+        fn build_config(sources: Option<&[&str]>, exclude: &[&str]) -> manifest::BuildConfig {
+            manifest::BuildConfig {
+                sources: sources.map(|s| s.iter().map(ToString::to_string).collect()),
+                exclude: exclude.iter().map(ToString::to_string).collect(),
+                ..Default::default()
+            }
+        }
 
+        fn ensure_found_expected_files(
+            found: &BTreeSet<PathBuf>,
+            expected: &BTreeSet<PathBuf>,
+        ) -> anyhow::Result<()> {
             anyhow::ensure!(
-                project_name == "foo",
+                found == expected,
                 format!(
-                    "Got wrong project name!\nExpected: foo\nGot: {}\n",
-                    project_name
+                    "Failed to gather expected source files!\nGot: {:?}.\nExpected: {:?}",
+                    found, expected
                 )
             );
 
@@ -411,29 +2279,127 @@ mod tests {
         }
 
         #[test]
-        fn no_name_in_manifest() -> anyhow::Result<()> {
-            let tmp_dir = assert_fs::TempDir::new()?;
-            let project_manifest = tmp_dir.child("Cppargo.toml");
-            project_manifest.write_str(PROJEJCT_MANIFEST_WITH_NO_NAME)?;
+        fn default_to_src_dir() -> anyhow::Result<()> {
+            let project_root = assert_fs::TempDir::new()?;
+            project_root.child("src/main.cpp").touch()?;
+            project_root.child("other/other.cpp").touch()?;
 
-            match get_project_name(project_manifest.path()) {
-                Err(err) => {
-                    if err.to_string()
-                        == format!(
-                            "Failed to parse project manifest {}!",
-                            project_manifest.path().display()
-                        )
-                    {
-                        return Ok(());
-                    }
+            let found_src_files = collect_src_files(&project_root, &build_config(None, &[]))?;
+            let expected_src_files = BTreeSet::from([PathBuf::from("src/main.cpp")]);
 
-                    anyhow::bail!(err);
-                }
-                Ok(name) => {
-                    anyhow::bail!(format!("Found unexpected name {name}!"));
-                }
-            }
+            ensure_found_expected_files(&found_src_files, &expected_src_files)
+        }
+
+        #[test]
+        fn exclude_from_src_dir() -> anyhow::Result<()> {
+            let project_root = assert_fs::TempDir::new()?;
+            project_root.child("src/main.cpp").touch()?;
+            project_root
+                .child("src/experimental/prototype.cpp")
+                .touch()?;
+
+            let found_src_files =
+                collect_src_files(&project_root, &build_config(None, &["src/experimental/**"]))?;
+            let expected_src_files = BTreeSet::from([PathBuf::from("src/main.cpp")]);
+
+            ensure_found_expected_files(&found_src_files, &expected_src_files)
+        }
+
+        #[test]
+        fn only_matching_sources() -> anyhow::Result<()> {
+            let project_root = assert_fs::TempDir::new()?;
+            project_root.child("src/main.cpp").touch()?;
+            project_root.child("src/nested/nested.cpp").touch()?;
+            project_root.child("src/c_file.c").touch()?;
+            project_root.child("src/header.hpp").touch()?;
+            project_root.child("vendor/sqlite3.c").touch()?;
+
+            let found_src_files = collect_src_files(
+                &project_root,
+                &build_config(Some(&["src/**/*.cpp", "vendor/sqlite3.c"]), &[]),
+            )?;
+            let expected_src_files = BTreeSet::from(
+                ["src/main.cpp", "src/nested/nested.cpp", "vendor/sqlite3.c"].map(PathBuf::from),
+            );
+
+            ensure_found_expected_files(&found_src_files, &expected_src_files)
+        }
+
+        #[test]
+        fn overlapping_globs_with_strict_sources() -> anyhow::Result<()> {
+            let project_root = assert_fs::TempDir::new()?;
+            project_root.child("src/main.cpp").touch()?;
+            project_root.child("src/foo/foo.cpp").touch()?;
+            project_root.child("src/foo/bar.c").touch()?;
+
+            let build_config = manifest::BuildConfig {
+                strict_sources: true,
+                ..build_config(Some(&["src/**/*.cpp", "src/**/*.c", "src/foo/*.cpp"]), &[])
+            };
+            let found_src_files = collect_src_files(&project_root, &build_config)?;
+            let expected_src_files = BTreeSet::from(
+                ["src/foo/bar.c", "src/foo/foo.cpp", "src/main.cpp"].map(PathBuf::from),
+            );
+
+            ensure_found_expected_files(&found_src_files, &expected_src_files)
+        }
+
+        #[test]
+        fn single_star_does_not_cross_directories() -> anyhow::Result<()> {
+            let project_root = assert_fs::TempDir::new()?;
+            project_root.child("src/main.cpp").touch()?;
+            project_root.child("src/nested/nested.cpp").touch()?;
+
+            let found_src_files =
+                collect_src_files(&project_root, &build_config(Some(&["src/*.cpp"]), &[]))?;
+            let expected_src_files = BTreeSet::from([PathBuf::from("src/main.cpp")]);
+
+            ensure_found_expected_files(&found_src_files, &expected_src_files)
+        }
+
+        #[test]
+        fn fail_all_excluded() -> anyhow::Result<()> {
+            let project_root = assert_fs::TempDir::new()?;
+            project_root.child("src/main.cpp").touch()?;
+
+            anyhow::ensure!(
+                collect_src_files(&project_root, &build_config(None, &["src/**"])).is_err(),
+                "Didn't fail when excluding every source file!"
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn fail_invalid_glob() -> anyhow::Result<()> {
+            let project_root = assert_fs::TempDir::new()?;
+            project_root.child("src/main.cpp").touch()?;
+
+            anyhow::ensure!(
+                collect_src_files(&project_root, &build_config(Some(&["src/[*.cpp"]), &[]))
+                    .is_err(),
+                "Accepted an invalid glob!"
+            );
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn proper_glob_base() -> anyhow::Result<()> {
+        for (glob, expected) in [
+            ("src/**/*.cpp", "src"),
+            ("src/experimental/**", "src/experimental"),
+            ("vendor/sqlite3.c", "vendor/sqlite3.c"),
+            ("*.cpp", ""),
+        ] {
+            anyhow::ensure!(
+                glob_base(glob) == Path::new(expected),
+                format!("Got wrong base for `{glob}`: {:?}!", glob_base(glob))
+            );
         }
+
+        Ok(())
     }
 
     #[cfg(test)]
@@ -479,11 +2445,17 @@ mod tests {
         let module_file = project_src.child("module.hpp");
         module_file.write_str(MODULE_FILE)?;
 
-        let project_binary = project_target.child("foo");
-
-        let src_files = HashSet::from([main_file.to_path_buf()]);
+        let src_files = [PathBuf::from("src/main.cpp")];
         let project_name = "foo";
-        build_src_files(src_files, project_binary.path())?;
+        build_src_files(
+            &src_files,
+            &project_root,
+            Path::new("target/obj"),
+            Some(Path::new("target/foo")),
+            None,
+            &Toolchain::default(),
+            &Reporter::new(MessageFormat::Human),
+        )?;
         project_target
             .child(project_name)
             .assert(predicates::path::is_file());
@@ -491,6 +2463,499 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn build_c_and_cpp_src_files() -> anyhow::Result<()> {
+        let tmp_dir = assert_fs::TempDir::new()?;
+        let project_root = tmp_dir.child("foo");
+        let project_src = project_root.child("src");
+        let project_target = project_root.child("target");
+        project_target.create_dir_all()?;
+
+        let main_file = project_src.child("main.cpp");
+        main_file.write_str(MAIN_FILE_CALLING_C)?;
+        let c_file = project_src.child("c_module.c");
+        c_file.write_str(C_MODULE_FILE)?;
+
+        let project_binary = project_target.child("foo");
+
+        let src_files = ["src/c_module.c", "src/main.cpp"].map(PathBuf::from);
+        let toolchain = Toolchain {
+            c_flags: vec!["-std=c99".to_string()],
+            ..Default::default()
+        };
+        build_src_files(
+            &src_files,
+            &project_root,
+            Path::new("target/obj"),
+            Some(Path::new("target/foo")),
+            None,
+            &toolchain,
+            &Reporter::new(MessageFormat::Human),
+        )?;
+        project_binary.assert(predicates::path::is_file());
+        project_target
+            .child("obj/src/c_module.c.o")
+            .assert(predicates::path::is_file());
+
+        Ok(())
+    }
+
+    #[test]
+    fn proper_compile_command() -> anyhow::Result<()> {
+        let toolchain = Toolchain {
+            c_flags: vec!["-std=c11".to_string()],
+            compile_flags: vec!["-Wall".to_string()],
+            ..Default::default()
+        };
+
+        let compiler = compile_command(
+            Path::new("src/main.cpp"),
+            Path::new("target/obj/src/main.cpp.o"),
+            &toolchain,
+        )?;
+        anyhow::ensure!(
+            compiler.get_program() == CPP_COMPILER
+                && compiler.get_args().collect::<Vec<_>>()
+                    == [
+                        "-Wall",
+                        "-fdiagnostics-parseable-fixits",
+                        "-c",
+                        "src/main.cpp",
+                        "-o",
+                        "target/obj/src/main.cpp.o"
+                    ],
+            format!("Got unexpected compile command {compiler:?}!")
+        );
+
+        let compiler = compile_command(
+            Path::new("src/lib.c"),
+            Path::new("target/obj/src/lib.c.o"),
+            &toolchain,
+        )?;
+        anyhow::ensure!(
+            compiler.get_program() == C_COMPILER
+                && compiler.get_args().collect::<Vec<_>>()
+                    == [
+                        "-std=c11",
+                        "-Wall",
+                        "-fdiagnostics-parseable-fixits",
+                        "-c",
+                        "src/lib.c",
+                        "-o",
+                        "target/obj/src/lib.c.o"
+                    ],
+            format!("Got unexpected compile command {compiler:?}!")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn deterministic_link_command() -> anyhow::Result<()> {
+        let project_root = assert_fs::TempDir::new()?;
+        let src_files = ["src/main.cpp", "src/b.cpp", "src/a/z.c", "src/a.cpp"];
+        for src_file in src_files {
+            project_root.child(src_file).touch()?;
+        }
+
+        let src_files = collect_src_files(&project_root, &manifest::BuildConfig::default())?;
+        let object_files: Vec<PathBuf> = src_files
+            .iter()
+            .map(|f| object_file_path(f, Path::new("target/obj")))
+            .collect();
+        let linker = link_command(
+            &object_files,
+            Path::new("target/foo"),
+            &Toolchain::default(),
+        );
+
+        anyhow::ensure!(
+            linker.get_args().collect::<Vec<_>>()
+                == [
+                    "-o",
+                    "target/foo",
+                    "target/obj/src/a/z.c.o",
+                    "target/obj/src/a.cpp.o",
+                    "target/obj/src/b.cpp.o",
+                    "target/obj/src/main.cpp.o",
+                ],
+            format!("Got unexpected link command {linker:?}!")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn proper_compile_commands() -> anyhow::Result<()> {
+        let project_root = Path::new("/project");
+        let src_files = ["src/lib.c", "src/main.cpp"].map(PathBuf::from);
+        let toolchain = Toolchain {
+            c_flags: vec!["-std=c11".to_string()],
+            ..Default::default()
+        };
+
+        let compile_commands = compile_commands(
+            &src_files,
+            project_root,
+            Path::new("target/obj"),
+            &toolchain,
+        )?;
+        let expected = serde_json::json!([
+            {
+                "directory": "/project",
+                "file": "src/lib.c",
+                "arguments": [
+                    C_COMPILER,
+                    "-std=c11",
+                    "-fdiagnostics-parseable-fixits",
+                    "-c",
+                    "src/lib.c",
+                    "-o",
+                    "target/obj/src/lib.c.o",
+                ],
+                "output": "target/obj/src/lib.c.o",
+            },
+            {
+                "directory": "/project",
+                "file": "src/main.cpp",
+                "arguments": [
+                    CPP_COMPILER,
+                    "-fdiagnostics-parseable-fixits",
+                    "-c",
+                    "src/main.cpp",
+                    "-o",
+                    "target/obj/src/main.cpp.o",
+                ],
+                "output": "target/obj/src/main.cpp.o",
+            },
+        ]);
+
+        anyhow::ensure!(
+            compile_commands == expected,
+            format!("Got unexpected compile commands:\n{compile_commands:#}")
+        );
+
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod find_programs {
+        use super::*;
+
+        fn examples_project() -> anyhow::Result<assert_fs::TempDir> {
+            let project_root = assert_fs::TempDir::new()?;
+            project_root.child("examples/hello.cpp").touch()?;
+            project_root.child("examples/legacy.c").touch()?;
+            project_root.child("examples/common.hpp").touch()?;
+            project_root.child("examples/data/input.cpp").touch()?;
+
+            Ok(project_root)
+        }
+
+        #[test]
+        fn find_all() -> anyhow::Result<()> {
+            let project_root = examples_project()?;
+
+            let programs = find_programs(&project_root, "examples", &Programs::All)?;
+            let expected = BTreeMap::from([
+                ("hello".to_string(), PathBuf::from("examples/hello.cpp")),
+                ("legacy".to_string(), PathBuf::from("examples/legacy.c")),
+            ]);
+
+            anyhow::ensure!(
+                programs == expected,
+                format!("Found unexpected programs {programs:?}!")
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn find_named() -> anyhow::Result<()> {
+            let project_root = examples_project()?;
+
+            let programs = find_programs(
+                &project_root,
+                "examples",
+                &Programs::Named("hello".to_string()),
+            )?;
+
+            anyhow::ensure!(
+                programs.keys().eq(["hello"]),
+                format!("Found unexpected programs {programs:?}!")
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn fail_missing_named() -> anyhow::Result<()> {
+            let project_root = examples_project()?;
+
+            anyhow::ensure!(
+                find_programs(
+                    &project_root,
+                    "examples",
+                    &Programs::Named("common".to_string()),
+                )
+                .is_err(),
+                "Found a program without a source file!"
+            );
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn proper_make_dependencies() -> anyhow::Result<()> {
+        let dependencies = make_dependencies(concat!(
+            "target/pch/pch.hpp.gch: src/pch.hpp /usr/include/c++/12/vector \\\n",
+            " src/my\\ config.hpp\n"
+        ));
+
+        anyhow::ensure!(
+            dependencies
+                == [
+                    PathBuf::from("src/pch.hpp"),
+                    PathBuf::from("/usr/include/c++/12/vector"),
+                    PathBuf::from("src/my config.hpp"),
+                ],
+            format!("Got unexpected dependencies {dependencies:?}!")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn proper_relative_path() -> anyhow::Result<()> {
+        for (dir, path, project_root, expected) in [
+            ("target/pch", "src/pch.hpp", "/foo", "../../src/pch.hpp"),
+            (
+                "../../target/release/pch/core",
+                "src/pch.hpp",
+                "/workspace/libs/core",
+                "../../../../libs/core/src/pch.hpp",
+            ),
+        ] {
+            let relative = relative_path(Path::new(dir), Path::new(path), Path::new(project_root));
+            anyhow::ensure!(
+                relative == Path::new(expected),
+                format!(
+                    "Got unexpected path {} from {dir} to {path}!",
+                    relative.display()
+                )
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn proper_output_dir() -> anyhow::Result<()> {
+        for (options, expected) in [
+            (Options::default(), "target"),
+            (
+                Options {
+                    profile: manifest::Profile::Release,
+                    ..Default::default()
+                },
+                "target/release",
+            ),
+            (
+                Options {
+                    target: Some("aarch64-linux-gnu".to_string()),
+                    ..Default::default()
+                },
+                "target/aarch64-linux-gnu/debug",
+            ),
+            (
+                Options {
+                    target: Some("aarch64-linux-gnu".to_string()),
+                    profile: manifest::Profile::Release,
+                    sanitizers: BTreeSet::from([Sanitizer::Address]),
+                    ..Default::default()
+                },
+                "target/aarch64-linux-gnu/release/sanitize-address",
+            ),
+        ] {
+            let output_dir = output_dir(&options);
+            anyhow::ensure!(
+                output_dir == Path::new(expected),
+                format!("Got unexpected output directory {}!", output_dir.display())
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn cross_toolchain() -> anyhow::Result<()> {
+        let tmp_dir = assert_fs::TempDir::new()?;
+        let project_manifest = tmp_dir.child("Cppargo.toml");
+        project_manifest.write_str(concat!(
+            "[project]\nname = \"foo\"\n",
+            "[target.aarch64-linux-gnu]\n",
+            "sysroot = \"/usr/aarch64-linux-gnu\"\n",
+            "flags = [\"-mcpu=cortex-a72\"]\n",
+            "link-flags = [\"-static\"]\n",
+        ))?;
+        let manifest = manifest::read(project_manifest.path())?;
+        let defines = package_defines(&manifest);
+
+        for (triple, cpp_compiler, c_compiler, compile_flags, link_flags) in [
+            (
+                "aarch64-linux-gnu",
+                "aarch64-linux-gnu-g++",
+                "aarch64-linux-gnu-gcc",
+                vec!["--sysroot=/usr/aarch64-linux-gnu", "-mcpu=cortex-a72"],
+                vec!["--sysroot=/usr/aarch64-linux-gnu", "-static"],
+            ),
+            (
+                "arm-linux-gnueabihf",
+                "arm-linux-gnueabihf-g++",
+                "arm-linux-gnueabihf-gcc",
+                vec![],
+                vec![],
+            ),
+        ] {
+            let options = Options {
+                target: Some(triple.to_string()),
+                ..Default::default()
+            };
+            let toolchain = Toolchain::new(&manifest, &manifest.build, &options);
+
+            anyhow::ensure!(
+                toolchain.cpp_compiler == cpp_compiler
+                    && toolchain.c_compiler == c_compiler
+                    && toolchain.compile_flags[..compile_flags.len()] == compile_flags
+                    && toolchain.compile_flags[compile_flags.len()..] == defines
+                    && toolchain.link_flags == link_flags,
+                format!("Got unexpected toolchain for `{triple}`!")
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn package_metadata_defines() -> anyhow::Result<()> {
+        let tmp_dir = assert_fs::TempDir::new()?;
+        let project_manifest = tmp_dir.child("Cppargo.toml");
+        project_manifest.write_str(PROJECT_MANIFEST)?;
+        let defines = package_defines(&manifest::read(project_manifest.path())?);
+        anyhow::ensure!(
+            defines
+                == [
+                    "-DCPPARGO_PKG_NAME=\"foo\"",
+                    "-DCPPARGO_PKG_VERSION=\"\"",
+                    "-DCPPARGO_PKG_AUTHORS=\"\"",
+                    "-DCPPARGO_PKG_DESCRIPTION=\"\"",
+                    "-DCPPARGO_PKG_LICENSE=\"\"",
+                ],
+            format!("Got unexpected defines {defines:?}!")
+        );
+
+        project_manifest.write_str(concat!(
+            "[project]\n",
+            "name = \"foo\"\n",
+            "version = \"1.08.3-beta\"\n",
+            "authors = [\"Jane\", \"John\"]\n",
+            "description = \"\"\"A \\\"foo\\\"\\\\\nproject\"\"\"\n",
+        ))?;
+        let defines = package_defines(&manifest::read(project_manifest.path())?);
+        anyhow::ensure!(
+            defines
+                == [
+                    "-DCPPARGO_PKG_NAME=\"foo\"",
+                    "-DCPPARGO_PKG_VERSION=\"1.08.3-beta\"",
+                    "-DCPPARGO_PKG_AUTHORS=\"Jane:John\"",
+                    "-DCPPARGO_PKG_DESCRIPTION=\"A \\\"foo\\\"\\\\\\nproject\"",
+                    "-DCPPARGO_PKG_LICENSE=\"\"",
+                    "-DCPPARGO_PKG_VERSION_MAJOR=1",
+                    "-DCPPARGO_PKG_VERSION_MINOR=8",
+                    "-DCPPARGO_PKG_VERSION_PATCH=3",
+                ],
+            format!("Got unexpected defines {defines:?}!")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn link_time_optimization() -> anyhow::Result<()> {
+        anyhow::ensure!(
+            lto_flags(manifest::Lto::Off, CPP_COMPILER).is_empty(),
+            "Enabled link-time optimization while off!"
+        );
+        let full_lto = lto_flags(manifest::Lto::Full, CPP_COMPILER);
+        anyhow::ensure!(
+            full_lto == ["-flto=auto", "-flto-partition=one"] || full_lto == ["-flto=full"],
+            format!("Got unexpected full LTO flags {full_lto:?}!")
+        );
+
+        // A compiler which can't link anything can't use the linker either.
+        let toolchain = Toolchain {
+            cpp_compiler: "false".to_string(),
+            ..Default::default()
+        };
+        let profile = manifest::ProfileConfig {
+            linker: Some(manifest::Linker::Mold),
+            ..manifest::Profiles::default().release
+        };
+        let Err(err) = ensure_supported(&toolchain, &profile, &Options::default()) else {
+            anyhow::bail!("Accepted a linker the compiler can't use!");
+        };
+        anyhow::ensure!(
+            err.to_string()
+                .contains("Compiler `false` can't link with `mold`!"),
+            format!("Got unexpected error: {err}")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn proper_lint_flags() -> anyhow::Result<()> {
+        for (level, deny_warnings, expected) in [
+            (manifest::WarningLevel::None, false, vec!["-w"]),
+            (manifest::WarningLevel::Default, false, vec![]),
+            (manifest::WarningLevel::All, true, vec!["-Wall", "-Werror"]),
+            (
+                manifest::WarningLevel::Extra,
+                false,
+                vec!["-Wall", "-Wextra"],
+            ),
+            (
+                manifest::WarningLevel::Pedantic,
+                false,
+                vec!["-Wall", "-Wextra", "-Wpedantic"],
+            ),
+        ] {
+            let lints = manifest::LintsConfig {
+                level,
+                ..Default::default()
+            };
+            let flags = lint_flags(&lints, deny_warnings);
+            anyhow::ensure!(
+                flags == expected,
+                format!("Got unexpected flags {flags:?} for {level:?}!")
+            );
+        }
+
+        let lints = manifest::LintsConfig {
+            level: manifest::WarningLevel::All,
+            flags: vec!["-Wshadow".to_string()],
+            deny_warnings: false,
+        };
+        let flags = lint_flags(&lints, true);
+        anyhow::ensure!(
+            flags == ["-Wall", "-Wshadow", "-Werror"],
+            format!("Got unexpected flags {flags:?}!")
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn proper_main() -> anyhow::Result<()> {
         let project_root = assert_fs::TempDir::new()?;
@@ -506,7 +2971,11 @@ mod tests {
         let module_file = project_src.child("module.hpp");
         module_file.write_str(MODULE_FILE)?;
 
-        main(&project_root)?;
+        main(
+            &project_root,
+            &Options::default(),
+            &Reporter::new(MessageFormat::Human),
+        )?;
         project_target
             .child("foo")
             .assert(predicates::path::is_file());
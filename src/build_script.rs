@@ -0,0 +1,202 @@
+use crate::Context;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// Prefix of the lines a build script prints to configure the build, such as
+/// `cppargo:include=target/gen`.
+const PREFIX: &str = "cppargo:";
+
+/// What a build script asked for, through the `cppargo:KEY=VALUE` lines it
+/// printed.
+#[derive(Debug, Default, PartialEq)]
+pub struct Output {
+    /// Directories to search for headers, given with `include`.
+    pub include_dirs: Vec<PathBuf>,
+    /// Macros to define as `NAME` or `NAME=VALUE`, given with `define`.
+    pub defines: Vec<String>,
+    /// Directories to search for libraries, given with `link-search`.
+    pub link_search_dirs: Vec<PathBuf>,
+    /// Libraries to link, given with `link-lib`.
+    pub link_libs: Vec<String>,
+    /// Generated source files to compile along with the project sources,
+    /// given with `source`.
+    pub sources: Vec<PathBuf>,
+    /// Files whose changes make the script run again, given with
+    /// `rerun-if-changed`. Without any, the script runs on every build.
+    pub rerun_if_changed: Vec<PathBuf>,
+    /// Messages to show the user, given with `warning`.
+    pub warnings: Vec<String>,
+}
+
+/// Run a build script `command` from the project root, and save what it
+/// printed to `output_file` so that it can be reused by later builds.
+pub fn run(mut command: Command, output_file: &Path) -> anyhow::Result<Output> {
+    let output = command
+        .stderr(Stdio::inherit())
+        .output()
+        .with_context(|| format!("Couldn't start build script: {command:?}"))?;
+    anyhow::ensure!(
+        output.status.success(),
+        format!("Build script exited with {}!", output.status)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let parsed = parse(&stdout)?;
+    fs::write(output_file, stdout.as_bytes()).with_context(|| {
+        format!(
+            "Failed to save build script output {}!",
+            output_file.display()
+        )
+    })?;
+
+    Ok(parsed)
+}
+
+/// The output saved by a previous run of the build script, unless it has to
+/// run again, because it didn't ask to only rerun when given files change, or
+/// because any of those or of the `script_files` changed since.
+///
+/// Paths are relative to `project_root`.
+pub fn reuse(
+    project_root: &Path,
+    output_file: &Path,
+    script_files: &[PathBuf],
+) -> anyhow::Result<Option<Output>> {
+    let Ok(saved) = fs::metadata(output_file).and_then(|metadata| metadata.modified()) else {
+        return Ok(None);
+    };
+
+    let output = parse(&fs::read_to_string(output_file)?)?;
+    if output.rerun_if_changed.is_empty() {
+        return Ok(None);
+    }
+    let unchanged = script_files
+        .iter()
+        .chain(&output.rerun_if_changed)
+        .all(|file| {
+            fs::metadata(project_root.join(file))
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(|modified| modified <= saved)
+        });
+
+    Ok(unchanged.then_some(output))
+}
+
+/// Parse the `cppargo:KEY=VALUE` lines of a build script's `stdout`, ignoring
+/// any other line.
+fn parse(stdout: &str) -> anyhow::Result<Output> {
+    let mut output = Output::default();
+    for line in stdout.lines() {
+        let Some(instruction) = line.strip_prefix(PREFIX) else {
+            continue;
+        };
+        let Some((key, value)) = instruction.split_once('=') else {
+            anyhow::bail!(format!(
+                "Invalid build script line `{line}`! Expected `{PREFIX}KEY=VALUE`."
+            ));
+        };
+
+        match key {
+            "include" => output.include_dirs.push(value.into()),
+            "define" => output.defines.push(value.to_string()),
+            "link-search" => output.link_search_dirs.push(value.into()),
+            "link-lib" => output.link_libs.push(value.to_string()),
+            "source" => output.sources.push(value.into()),
+            "rerun-if-changed" => output.rerun_if_changed.push(value.into()),
+            "warning" => output.warnings.push(value.to_string()),
+            _ => anyhow::bail!(format!("Unknown build script instruction `{PREFIX}{key}`!")),
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+    use std::{thread, time::Duration};
+
+    #[test]
+    fn proper_parse() -> anyhow::Result<()> {
+        let output = parse(concat!(
+            "Generating headers...\n",
+            "cppargo:include=target/gen\n",
+            "cppargo:define=VERSION=\"1.2.3\"\n",
+            "cppargo:define=HAVE_PROTOBUF\n",
+            "cppargo:link-search=/opt/protobuf/lib\n",
+            "cppargo:link-lib=protobuf\n",
+            "cppargo:source=target/gen/foo.pb.cc\n",
+            "cppargo:rerun-if-changed=proto/foo.proto\n",
+            "cppargo:warning=protoc is outdated\n"
+        ))?;
+        let expected = Output {
+            include_dirs: vec![PathBuf::from("target/gen")],
+            defines: vec!["VERSION=\"1.2.3\"".to_string(), "HAVE_PROTOBUF".to_string()],
+            link_search_dirs: vec![PathBuf::from("/opt/protobuf/lib")],
+            link_libs: vec!["protobuf".to_string()],
+            sources: vec![PathBuf::from("target/gen/foo.pb.cc")],
+            rerun_if_changed: vec![PathBuf::from("proto/foo.proto")],
+            warnings: vec!["protoc is outdated".to_string()],
+        };
+
+        anyhow::ensure!(
+            output == expected,
+            format!("Got unexpected output {output:?}!")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn fail_invalid_instruction() -> anyhow::Result<()> {
+        for line in ["cppargo:include", "cppargo:link=foo"] {
+            anyhow::ensure!(
+                parse(line).is_err(),
+                format!("Accepted invalid instruction `{line}`!")
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn reuse_until_changed() -> anyhow::Result<()> {
+        let project_root = assert_fs::TempDir::new()?;
+        let proto = project_root.child("foo.proto");
+        proto.write_str("message Foo {}\n")?;
+        let output_file = project_root.child("output");
+        let script_files = [PathBuf::from("foo.proto")];
+
+        anyhow::ensure!(
+            reuse(&project_root, &output_file, &script_files)?.is_none(),
+            "Reused missing output!"
+        );
+
+        // Modification times may be too coarse to tell writes apart.
+        thread::sleep(Duration::from_millis(50));
+        output_file.write_str("cppargo:rerun-if-changed=foo.proto\n")?;
+        anyhow::ensure!(
+            reuse(&project_root, &output_file, &script_files)?.is_some(),
+            "Didn't reuse output of unchanged script!"
+        );
+
+        thread::sleep(Duration::from_millis(50));
+        proto.write_str("message Bar {}\n")?;
+        anyhow::ensure!(
+            reuse(&project_root, &output_file, &script_files)?.is_none(),
+            "Reused output after a file changed!"
+        );
+
+        output_file.write_str("cppargo:include=gen\n")?;
+        anyhow::ensure!(
+            reuse(&project_root, &output_file, &script_files)?.is_none(),
+            "Reused output of script without `rerun-if-changed`!"
+        );
+
+        Ok(())
+    }
+}
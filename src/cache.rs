@@ -0,0 +1,322 @@
+use crate::Context;
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    process::{self, Command},
+    time::SystemTime,
+};
+
+/// Size the cache is trimmed to after every build, unless set otherwise.
+pub const DEFAULT_MAX_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// Object files compiled by any build, stored by a hash of everything the
+/// compiler reads, so that compiling the same source with the same flags
+/// again, such as after switching branches back, reuses them.
+pub struct ObjectCache {
+    dir: PathBuf,
+    /// `--version` output of each compiler, by program.
+    versions: RefCell<BTreeMap<OsString, Vec<u8>>>,
+}
+
+impl ObjectCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            versions: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// The key of the object file the `compiler` command outputs, hashing
+    /// the compiler version, the command line without the object file, the
+    /// directory it's run in, and the preprocessed source.
+    ///
+    /// There's no key if the source can't be preprocessed, so that compiling
+    /// it reports why.
+    pub fn key(&self, compiler: &Command) -> anyhow::Result<Option<String>> {
+        let mut hasher = Hasher::default();
+        hasher.write(&self.version(compiler.get_program())?);
+
+        let mut preprocessor = Command::new(compiler.get_program());
+        let mut args = compiler.get_args();
+        while let Some(arg) = args.next() {
+            if arg == "-o" {
+                args.next();
+                continue;
+            }
+            hasher.write(arg.as_encoded_bytes());
+            preprocessor.arg(if arg == "-c" { "-E".as_ref() } else { arg });
+        }
+        if let Some(dir) = compiler.get_current_dir() {
+            hasher.write(dir.as_os_str().as_encoded_bytes());
+            preprocessor.current_dir(dir);
+        }
+
+        let output = preprocessor
+            .output()
+            .with_context(|| format!("Couldn't start preprocessor: {preprocessor:?}"))?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        hasher.write(&output.stdout);
+
+        Ok(Some(hasher.finish()))
+    }
+
+    /// Copy the object file cached under `key` to `object_file`, returning
+    /// what the compiler printed when compiling it, if there's any.
+    pub fn get(&self, key: &str, object_file: &Path) -> Option<String> {
+        let (cached_object, cached_output) = self.paths(key);
+        fs::copy(&cached_object, object_file).ok()?;
+        // Entries are evicted by their modification time, so mark this one as
+        // recently used.
+        let _ = fs::File::options()
+            .write(true)
+            .open(&cached_object)
+            .and_then(|cached_object| cached_object.set_modified(SystemTime::now()));
+
+        Some(fs::read_to_string(cached_output).unwrap_or_default())
+    }
+
+    /// Store the `object_file` under `key`, along with what the compiler
+    /// `printed` when compiling it.
+    pub fn put(&self, key: &str, object_file: &Path, printed: &str) -> anyhow::Result<()> {
+        let (cached_object, cached_output) = self.paths(key);
+        if let Some(dir) = cached_object.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create directory {}!", dir.display()))?;
+        }
+
+        // Files are renamed into place, so that concurrent builds never read
+        // them half written, and the object last, since it marks the entry
+        // as complete.
+        let temporary = |path: &Path| {
+            let mut temporary = path.as_os_str().to_os_string();
+            temporary.push(format!(".{}.tmp", process::id()));
+            PathBuf::from(temporary)
+        };
+        let output_tmp = temporary(&cached_output);
+        fs::write(&output_tmp, printed)?;
+        fs::rename(&output_tmp, &cached_output)?;
+        let object_tmp = temporary(&cached_object);
+        fs::copy(object_file, &object_tmp)?;
+        fs::rename(&object_tmp, &cached_object)?;
+
+        Ok(())
+    }
+
+    fn paths(&self, key: &str) -> (PathBuf, PathBuf) {
+        // Entries are spread over subdirectories to keep them small.
+        let (prefix, rest) = key.split_at(2);
+        let entry = self.dir.join(prefix).join(rest);
+
+        (entry.with_extension("o"), entry.with_extension("out"))
+    }
+
+    fn version(&self, program: &std::ffi::OsStr) -> anyhow::Result<Vec<u8>> {
+        if let Some(version) = self.versions.borrow().get(program) {
+            return Ok(version.clone());
+        }
+
+        let output = Command::new(program)
+            .arg("--version")
+            .output()
+            .with_context(|| format!("Couldn't start compiler {}!", program.to_string_lossy()))?;
+        self.versions
+            .borrow_mut()
+            .insert(program.to_os_string(), output.stdout.clone());
+
+        Ok(output.stdout)
+    }
+}
+
+/// Remove the least recently used entries of the cache in `dir` until the
+/// rest take at most `max_size` bytes.
+pub fn trim(dir: &Path, max_size: u64) -> anyhow::Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+
+    let mut entries = Vec::new();
+    let mut total_size = 0;
+    for prefix_dir in
+        fs::read_dir(dir).with_context(|| format!("Failed to read {}!", dir.display()))?
+    {
+        let prefix_dir = prefix_dir?.path();
+        if !prefix_dir.is_dir() {
+            continue;
+        }
+        for file in fs::read_dir(&prefix_dir)? {
+            let cached_object = file?.path();
+            if cached_object.extension().is_none_or(|ext| ext != "o") {
+                continue;
+            }
+            let metadata = fs::metadata(&cached_object)?;
+            let size = metadata.len()
+                + fs::metadata(cached_object.with_extension("out"))
+                    .map_or(0, |output| output.len());
+            total_size += size;
+            entries.push((metadata.modified()?, size, cached_object));
+        }
+    }
+
+    entries.sort();
+    for (_, size, cached_object) in entries {
+        if total_size <= max_size {
+            break;
+        }
+        // The object goes first, since it marks the entry as complete.
+        fs::remove_file(&cached_object)
+            .with_context(|| format!("Failed to remove {}!", cached_object.display()))?;
+        let _ = fs::remove_file(cached_object.with_extension("out"));
+        total_size -= size;
+    }
+
+    Ok(())
+}
+
+/// 128-bit FNV-1a hasher, whose hashes, unlike those of the standard library,
+/// stay the same across Rust versions.
+struct Hasher(u128);
+
+impl Default for Hasher {
+    fn default() -> Self {
+        Self(0x6c62_272e_07bb_0142_62b8_2175_6295_c58d)
+    }
+}
+
+impl Hasher {
+    const PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
+    /// Hash `bytes`, prefixed with their length so that consecutive writes
+    /// can't be confused with a single one.
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in (bytes.len() as u64).to_le_bytes().iter().chain(bytes) {
+            self.0 ^= u128::from(*byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn finish(&self) -> String {
+        format!("{:032x}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+
+    #[test]
+    fn stable_hash() -> anyhow::Result<()> {
+        let mut hasher = Hasher::default();
+        hasher.write(b"");
+        let empty = hasher.finish();
+        hasher.write(b"int main() {}");
+
+        anyhow::ensure!(
+            empty == "9d30c1f78465995be47dda5e4e4e77ed" && hasher.finish() != empty,
+            format!("Got unexpected hash {empty}!")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn reuse_cached_objects() -> anyhow::Result<()> {
+        let tmp_dir = assert_fs::TempDir::new()?;
+        let src_file = tmp_dir.child("main.cpp");
+        src_file.write_str("int main() { return 0; }\n")?;
+        let object_file = tmp_dir.child("main.o");
+        let cache = ObjectCache::new(tmp_dir.join("cache"));
+
+        let compile = |flag: &str| {
+            let mut compiler = Command::new("g++");
+            compiler
+                .arg(flag)
+                .args(["-c", "main.cpp", "-o", "main.o"])
+                .current_dir(tmp_dir.path());
+            compiler
+        };
+        let Some(key) = cache.key(&compile("-O0"))? else {
+            anyhow::bail!("Failed to preprocess {}!", src_file.display());
+        };
+        anyhow::ensure!(
+            cache.get(&key, &object_file).is_none(),
+            "Found object in empty cache!"
+        );
+
+        compile("-O0").status()?;
+        cache.put(&key, &object_file, "warning: foo\n")?;
+        fs::remove_file(&object_file)?;
+        anyhow::ensure!(
+            cache.get(&key, &object_file).as_deref() == Some("warning: foo\n"),
+            "Didn't reuse cached object!"
+        );
+        object_file.assert(predicates::path::is_file());
+
+        // Changing either flags or sources changes the key.
+        anyhow::ensure!(
+            cache.key(&compile("-O2"))?.as_ref() != Some(&key),
+            "Reused key after flags changed!"
+        );
+        src_file.write_str("int main() { return 1; }\n")?;
+        anyhow::ensure!(
+            cache.key(&compile("-O0"))?.as_ref() != Some(&key),
+            "Reused key after source changed!"
+        );
+
+        src_file.write_str("#include \"missing.hpp\"\n")?;
+        anyhow::ensure!(
+            cache.key(&compile("-O0"))?.is_none(),
+            "Got key of source which can't be preprocessed!"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn trim_least_recently_used() -> anyhow::Result<()> {
+        let tmp_dir = assert_fs::TempDir::new()?;
+        let object_file = tmp_dir.child("main.o");
+        object_file.write_str(&"o".repeat(100))?;
+        let cache = ObjectCache::new(tmp_dir.join("cache"));
+
+        let keys = ["aa01", "bb02", "cc03"];
+        for (age, key) in keys.iter().enumerate() {
+            cache.put(key, &object_file, "")?;
+            let (cached_object, _) = cache.paths(key);
+            let modified =
+                SystemTime::now() - std::time::Duration::from_secs(60 * (3 - age as u64));
+            fs::File::options()
+                .write(true)
+                .open(cached_object)?
+                .set_modified(modified)?;
+        }
+        // Using the oldest entry keeps it over the others.
+        anyhow::ensure!(
+            cache.get("aa01", &object_file).is_some(),
+            "Didn't find cached object!"
+        );
+
+        trim(&tmp_dir.join("cache"), 250)?;
+        let kept: Vec<_> = keys
+            .into_iter()
+            .filter(|key| cache.paths(key).0.is_file())
+            .collect();
+        anyhow::ensure!(
+            kept == ["aa01", "cc03"],
+            format!("Kept unexpected entries {kept:?}!")
+        );
+
+        trim(&tmp_dir.join("cache"), 0)?;
+        anyhow::ensure!(
+            keys.iter().all(|key| !cache.paths(key).0.exists()),
+            "Didn't remove every entry!"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,329 @@
+use std::env;
+
+/// A `cfg(...)` predicate used to make manifest tables platform-conditional,
+/// such as `[target.'cfg(unix)'.build]`.
+#[derive(Debug, PartialEq)]
+pub enum Cfg {
+    /// A bare name, such as `unix` or `windows`.
+    Name(String),
+    /// A key-value pair, such as `target_os = "linux"`.
+    KeyValue(String, String),
+    All(Vec<Cfg>),
+    Any(Vec<Cfg>),
+    Not(Box<Cfg>),
+}
+
+/// The platform `cfg(...)` predicates are evaluated against.
+#[derive(Debug, PartialEq)]
+pub struct Platform {
+    pub os: String,
+    pub family: String,
+    pub arch: String,
+}
+
+impl Platform {
+    /// The platform `cppargo` is running on.
+    pub fn host() -> Self {
+        Self {
+            os: env::consts::OS.to_string(),
+            family: env::consts::FAMILY.to_string(),
+            arch: env::consts::ARCH.to_string(),
+        }
+    }
+
+    /// The platform described by a target triple, such as
+    /// `aarch64-linux-gnu` or `x86_64-w64-mingw32`, using the same names as
+    /// `Platform::host`.
+    pub fn from_triple(triple: &str) -> Self {
+        let mut components = triple.split('-');
+        let arch = match components.next().unwrap_or_default() {
+            "i386" | "i486" | "i586" | "i686" => "x86",
+            "arm64" => "aarch64",
+            arch if arch.starts_with("arm") || arch.starts_with("thumb") => "arm",
+            arch if arch.starts_with("riscv64") => "riscv64",
+            arch if arch.starts_with("riscv32") => "riscv32",
+            arch => arch,
+        };
+
+        let components: Vec<_> = components.collect();
+        let has = |names: &[&str]| {
+            components
+                .iter()
+                .any(|component| names.iter().any(|name| component.starts_with(name)))
+        };
+        let (os, family) = if has(&["linux"]) {
+            ("linux", "unix")
+        } else if has(&["darwin", "macos"]) {
+            ("macos", "unix")
+        } else if has(&["windows", "mingw", "cygwin"]) {
+            ("windows", "windows")
+        } else if has(&["freebsd"]) {
+            ("freebsd", "unix")
+        } else {
+            ("none", "")
+        };
+
+        Self {
+            os: os.to_string(),
+            family: family.to_string(),
+            arch: arch.to_string(),
+        }
+    }
+}
+
+impl Cfg {
+    /// Parse a full `cfg(...)` expression.
+    pub fn parse(expr: &str) -> anyhow::Result<Self> {
+        let mut parser = Parser::new(expr);
+        parser.expect_word("cfg")?;
+        parser.expect_char('(')?;
+        let cfg = parser.predicate()?;
+        parser.expect_char(')')?;
+        parser.skip_whitespace();
+        anyhow::ensure!(
+            parser.rest().is_empty(),
+            format!(
+                "Unexpected `{}` after `cfg(...)` in `{expr}`!",
+                parser.rest()
+            )
+        );
+
+        Ok(cfg)
+    }
+
+    pub fn matches(&self, platform: &Platform) -> bool {
+        match self {
+            Self::Name(name) => *name == platform.family,
+            Self::KeyValue(key, value) => match key.as_str() {
+                "target_os" => *value == platform.os,
+                "target_family" => *value == platform.family,
+                "target_arch" => *value == platform.arch,
+                _ => false,
+            },
+            Self::All(cfgs) => cfgs.iter().all(|cfg| cfg.matches(platform)),
+            Self::Any(cfgs) => cfgs.iter().any(|cfg| cfg.matches(platform)),
+            Self::Not(cfg) => !cfg.matches(platform),
+        }
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, position: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.rest().chars().next()
+    }
+
+    fn expect_char(&mut self, expected: char) -> anyhow::Result<()> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.position += c.len_utf8();
+                Ok(())
+            }
+            _ => anyhow::bail!(format!(
+                "Expected `{expected}` at `{}` in `{}`!",
+                self.rest(),
+                self.input
+            )),
+        }
+    }
+
+    fn word(&mut self) -> anyhow::Result<&'a str> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        anyhow::ensure!(
+            len > 0,
+            format!("Expected identifier at `{rest}` in `{}`!", self.input)
+        );
+        self.position += len;
+
+        Ok(&rest[..len])
+    }
+
+    fn expect_word(&mut self, expected: &str) -> anyhow::Result<()> {
+        let word = self.word()?;
+        anyhow::ensure!(
+            word == expected,
+            format!("Expected `{expected}`, found `{word}` in `{}`!", self.input)
+        );
+
+        Ok(())
+    }
+
+    fn string(&mut self) -> anyhow::Result<&'a str> {
+        self.expect_char('"')?;
+        let rest = self.rest();
+        let Some(len) = rest.find('"') else {
+            anyhow::bail!(format!("Unterminated string in `{}`!", self.input));
+        };
+        self.position += len + 1;
+
+        Ok(&rest[..len])
+    }
+
+    fn predicate(&mut self) -> anyhow::Result<Cfg> {
+        let word = self.word()?;
+
+        match word {
+            "all" | "any" | "not" => {
+                self.expect_char('(')?;
+                let mut cfgs = Vec::new();
+                while self.peek() != Some(')') {
+                    cfgs.push(self.predicate()?);
+                    if self.peek() == Some(',') {
+                        self.expect_char(',')?;
+                    } else {
+                        break;
+                    }
+                }
+                self.expect_char(')')?;
+
+                match word {
+                    "all" => Ok(Cfg::All(cfgs)),
+                    "any" => Ok(Cfg::Any(cfgs)),
+                    _ => {
+                        anyhow::ensure!(
+                            cfgs.len() == 1,
+                            format!("`not()` takes exactly one predicate in `{}`!", self.input)
+                        );
+                        Ok(Cfg::Not(Box::new(cfgs.remove(0))))
+                    }
+                }
+            }
+            key if self.peek() == Some('=') => {
+                self.expect_char('=')?;
+                let value = self.string()?;
+                Ok(Cfg::KeyValue(key.to_string(), value.to_string()))
+            }
+            name => Ok(Cfg::Name(name.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linux() -> Platform {
+        Platform {
+            os: "linux".to_string(),
+            family: "unix".to_string(),
+            arch: "x86_64".to_string(),
+        }
+    }
+
+    #[cfg(test)]
+    mod parse {
+        use super::*;
+
+        #[test]
+        fn name() -> anyhow::Result<()> {
+            let cfg = Cfg::parse("cfg(unix)")?;
+
+            anyhow::ensure!(
+                cfg == Cfg::Name("unix".to_string()),
+                format!("Got unexpected cfg {cfg:?}!")
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn nested() -> anyhow::Result<()> {
+            let cfg = Cfg::parse(r#"cfg(all(unix, not(target_os = "macos")))"#)?;
+            let expected = Cfg::All(vec![
+                Cfg::Name("unix".to_string()),
+                Cfg::Not(Box::new(Cfg::KeyValue(
+                    "target_os".to_string(),
+                    "macos".to_string(),
+                ))),
+            ]);
+
+            anyhow::ensure!(cfg == expected, format!("Got unexpected cfg {cfg:?}!"));
+
+            Ok(())
+        }
+
+        #[test]
+        fn fail_malformed() -> anyhow::Result<()> {
+            for expr in [
+                "unix",
+                "cfg(unix",
+                "cfg(unix) extra",
+                "cfg(not(unix, windows))",
+                r#"cfg(target_os = "linux)"#,
+            ] {
+                anyhow::ensure!(
+                    Cfg::parse(expr).is_err(),
+                    format!("Parsed malformed cfg `{expr}`!")
+                );
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn platform_from_triple() -> anyhow::Result<()> {
+        for (triple, os, family, arch) in [
+            ("x86_64-unknown-linux-gnu", "linux", "unix", "x86_64"),
+            ("aarch64-linux-gnu", "linux", "unix", "aarch64"),
+            ("armv7-unknown-linux-gnueabihf", "linux", "unix", "arm"),
+            ("i686-w64-mingw32", "windows", "windows", "x86"),
+            ("arm64-apple-darwin", "macos", "unix", "aarch64"),
+            ("riscv64gc-unknown-none-elf", "none", "", "riscv64"),
+        ] {
+            let platform = Platform::from_triple(triple);
+            anyhow::ensure!(
+                platform
+                    == Platform {
+                        os: os.to_string(),
+                        family: family.to_string(),
+                        arch: arch.to_string(),
+                    },
+                format!("Got unexpected platform {platform:?} for `{triple}`!")
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn matches_platform() -> anyhow::Result<()> {
+        for (expr, expected) in [
+            ("cfg(unix)", true),
+            ("cfg(windows)", false),
+            (r#"cfg(target_os = "linux")"#, true),
+            (r#"cfg(target_arch = "aarch64")"#, false),
+            (r#"cfg(any(windows, target_os = "linux"))"#, true),
+            (r#"cfg(all(unix, not(target_os = "linux")))"#, false),
+        ] {
+            anyhow::ensure!(
+                Cfg::parse(expr)?.matches(&linux()) == expected,
+                format!("Expected `{expr}` to evaluate to {expected}!")
+            );
+        }
+
+        Ok(())
+    }
+}
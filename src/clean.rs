@@ -0,0 +1,245 @@
+use crate::{build, workspace, Context};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Directories of every build inside `target/`, such as `target/release/obj`,
+/// where each workspace member has a subdirectory of its own.
+const MEMBER_DIRS: [&str; 4] = ["obj", "build", "pch", "modules"];
+
+/// Directories of every build inside `target/`, where the examples and
+/// benchmarks of every workspace member are linked.
+const PROGRAM_DIRS: [&str; 2] = ["examples", "benches"];
+
+/// Remove the `target/` directory of the project containing `current_dir`,
+/// which is shared by every member of a workspace, or only the files of the
+/// workspace member `package` inside it.
+///
+/// Returns the removed files and directories.
+pub fn main(current_dir: &Path, package: Option<&str>) -> anyhow::Result<Vec<PathBuf>> {
+    let project_root = build::find_project_root(current_dir).with_context(|| {
+        format!(
+            "Current directory {} is not inside a `cppargo` project!",
+            current_dir.display()
+        )
+    })?;
+    let workspace = workspace::find(&project_root)?;
+    let target_dir = workspace
+        .as_ref()
+        .map_or(project_root.as_path(), |workspace| workspace.root.as_path())
+        .join("target");
+
+    let mut removed = Vec::new();
+    match (package, &workspace) {
+        (None, _) => {
+            if target_dir.exists() {
+                removed.push(target_dir);
+            }
+        }
+        (Some(package), Some(workspace)) => {
+            let member_root = workspace.select(&project_root, Some(package))?.remove(0);
+            if target_dir.is_dir() {
+                let outputs = member_outputs(&member_root, package)?;
+                find_member_files(&target_dir, package, &outputs, &mut removed)?;
+
+                let bench_dir = member_root
+                    .strip_prefix(&workspace.root)
+                    .map(|member_dir| target_dir.join("bench").join(member_dir))?;
+                if bench_dir.is_dir() {
+                    removed.push(bench_dir);
+                }
+                removed.sort();
+            }
+        }
+        (Some(package), None) => anyhow::bail!(format!(
+            "Project {} is not inside a workspace to select the member `{package}` from!",
+            project_root.display()
+        )),
+    }
+
+    for path in &removed {
+        if path.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        }
+        .with_context(|| format!("Failed to remove {}!", path.display()))?;
+    }
+
+    Ok(removed)
+}
+
+/// Files the workspace member `name` at `member_root` links into every build,
+/// relative to it: its binary, its library archive, and its examples and
+/// benchmarks, along with their split debug information.
+fn member_outputs(member_root: &Path, name: &str) -> anyhow::Result<Vec<PathBuf>> {
+    let mut binaries = vec![PathBuf::from(name)];
+    for dir in PROGRAM_DIRS {
+        binaries.extend(
+            build::find_programs(member_root, dir, &build::Programs::All)?
+                .into_keys()
+                .map(|program| Path::new(dir).join(program)),
+        );
+    }
+
+    let mut outputs = vec![PathBuf::from(format!("lib{name}.a"))];
+    for binary in binaries {
+        let mut debug_file = binary.clone().into_os_string();
+        debug_file.push(".debug");
+        outputs.extend([binary, PathBuf::from(debug_file)]);
+    }
+
+    Ok(outputs)
+}
+
+/// Find the files of the workspace member `name` inside every build within
+/// `dir`: its directories, such as `target/release/obj/NAME`, and its
+/// `outputs`, such as `target/release/libNAME.a`.
+fn find_member_files(
+    dir: &Path,
+    name: &str,
+    outputs: &[PathBuf],
+    member_files: &mut Vec<PathBuf>,
+) -> anyhow::Result<()> {
+    // Only builds of the member, which always have its objects, have its
+    // outputs.
+    if dir.join("obj").join(name).is_dir() {
+        member_files.extend(
+            outputs
+                .iter()
+                .map(|output| dir.join(output))
+                .filter(|output| output.is_file()),
+        );
+    }
+
+    let mut entries = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}!", dir.display()))?
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(fs::DirEntry::file_name);
+
+    for entry in entries {
+        // Symlinks are never followed out of `target/`.
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let path = entry.path();
+        if MEMBER_DIRS
+            .iter()
+            .any(|member_dir| entry.file_name() == *member_dir)
+        {
+            let member_dir = path.join(name);
+            if member_dir.is_dir() {
+                member_files.push(member_dir);
+            }
+        } else if !PROGRAM_DIRS
+            .iter()
+            .any(|program_dir| entry.file_name() == *program_dir)
+        {
+            find_member_files(&path, name, outputs, member_files)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+
+    #[test]
+    fn clean_workspace_member() -> anyhow::Result<()> {
+        let workspace_root = assert_fs::TempDir::new()?;
+        workspace_root
+            .child("Cppargo.toml")
+            .write_str("[workspace]\nmembers = [\"libs/*\", \"apps/*\"]\n")?;
+        for (dir, name) in [("libs/core", "core"), ("apps/cli", "cli")] {
+            workspace_root
+                .child(dir)
+                .child("Cppargo.toml")
+                .write_str(&format!("[project]\nname = \"{name}\"\n"))?;
+        }
+        workspace_root
+            .child("libs/core/examples/demo.cpp")
+            .touch()?;
+        workspace_root
+            .child("libs/core/benches/speed.cpp")
+            .touch()?;
+        workspace_root
+            .child("apps/cli/examples/usage.cpp")
+            .touch()?;
+        let target = workspace_root.child("target");
+        for dir in [
+            "obj/core/src",
+            "obj/cli/src",
+            "pch/core",
+            "release/obj/core",
+            "release/modules/core",
+            "aarch64-linux-gnu/debug/build/core/out",
+        ] {
+            target.child(dir).create_dir_all()?;
+        }
+        for file in [
+            "libcore.a",
+            "cli",
+            "examples/demo",
+            "examples/usage",
+            "release/core",
+            "release/core.debug",
+            "release/benches/speed",
+            "bench/libs/core/baseline.json",
+        ] {
+            target.child(file).touch()?;
+        }
+
+        let removed = main(&workspace_root.child("apps/cli"), Some("core"))?;
+        anyhow::ensure!(
+            removed
+                == [
+                    "aarch64-linux-gnu/debug/build/core",
+                    "bench/libs/core",
+                    "examples/demo",
+                    "libcore.a",
+                    "obj/core",
+                    "pch/core",
+                    "release/benches/speed",
+                    "release/core",
+                    "release/core.debug",
+                    "release/modules/core",
+                    "release/obj/core",
+                ]
+                .map(|path| target.join(path)),
+            format!("Removed unexpected files {removed:?}!")
+        );
+        for path in &removed {
+            anyhow::ensure!(
+                !path.exists(),
+                format!("Failed to remove {}!", path.display())
+            );
+        }
+        for path in ["obj/cli", "cli", "examples/usage"] {
+            anyhow::ensure!(
+                target.child(path).exists(),
+                format!("Removed {} of another member!", path)
+            );
+        }
+
+        anyhow::ensure!(
+            main(&workspace_root, Some("missing")).is_err(),
+            "Cleaned a missing workspace member!"
+        );
+
+        anyhow::ensure!(
+            main(&workspace_root.child("apps/cli"), None)? == [target.to_path_buf()],
+            "Failed to remove the shared target directory!"
+        );
+        target.assert(predicates::path::missing());
+        anyhow::ensure!(
+            main(&workspace_root, None)?.is_empty(),
+            "Removed a missing target directory!"
+        );
+
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 pub use clap::{Parser, Subcommand};
 
+use crate::{message::MessageFormat, sanitizer::Sanitizer};
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -23,12 +24,22 @@ pub enum Commands {
     /// `git` repo. If it fails to do so because `git` is not found, it will
     /// simply issue a warning and continue.
     ///
+    /// The project name is taken from the last component of `<PATH>`, unless
+    /// given explicitly with `--name`. A valid project name is non-empty,
+    /// starts with an ASCII letter or `_`, and otherwise only contains ASCII
+    /// letters, digits, `_` or `-`. If the name is invalid, a sanitized
+    /// alternative is suggested.
+    ///
     /// This command fails if `<PATH>` already exists.
     #[command(visible_alias = "n")]
     New {
         // Path where the project will be created.
         #[arg(required = true)]
         path: PathBuf,
+        /// Set the project name in the manifest instead of using the directory
+        /// name.
+        #[arg(long)]
+        name: Option<String>,
     },
     /// Compile a project.
     ///
@@ -37,17 +48,311 @@ pub enum Commands {
     /// the project name, to use it for the compiled binary file name.
     ///
     /// Iterates over the project `PROJECT_ROOT/src` directory in order to
-    /// find all `.cpp` source files and give them to the `g++` compiler. The
+    /// find all C++ (`.cpp`, `.cc`, `.cxx`, `.c++`) and C (`.c`) source
+    /// files. Each one is compiled into an object file under
+    /// `PROJECT_ROOT/target/obj`, C++ files with `g++` and C files with `gcc`,
+    /// and then all of them are linked together with `g++`. The C standard
+    /// can be set with `c-standard` in the manifest `[build]` table. The
     /// compiled binary file is stored at `PROJECT_ROOT/target/PROJECT_NAME`.
     /// If the `PROJECT_ROOT/target` directory doesn't already exist, it
     /// creates it before compiling.
+    ///
+    /// With `--compile-commands`, or `compile-commands = true` in the manifest
+    /// `[build]` table, a `PROJECT_ROOT/compile_commands.json` compilation
+    /// database is also written for tools such as `clangd` and `clang-tidy`.
+    ///
+    /// Compiler diagnostics are parsed and re-rendered with project-relative
+    /// paths, followed by a summary of the warnings and errors of the build.
+    /// Which warnings are enabled, and whether they fail the build, is set
+    /// with the manifest `[lints]` table or `--deny-warnings`.
+    ///
+    /// With `--examples`, every C++ or C source file in
+    /// `PROJECT_ROOT/examples` is also built as an example program, linked
+    /// along with every project source file except `src/main.cpp`, into
+    /// `PROJECT_ROOT/target/examples/NAME`. Library projects, without
+    /// `src/main.cpp`, can only be built along with their examples or
+    /// benchmarks.
+    ///
+    /// The `name`, `version`, `authors`, `description` and `license` of the
+    /// manifest `[project]` table are defined as the `CPPARGO_PKG_NAME`,
+    /// `CPPARGO_PKG_VERSION`, `CPPARGO_PKG_AUTHORS`, `CPPARGO_PKG_DESCRIPTION`
+    /// and `CPPARGO_PKG_LICENSE` string macros, along with the
+    /// `CPPARGO_PKG_VERSION_MAJOR`, `_MINOR` and `_PATCH` numbers.
+    ///
+    /// With `--release`, the project is built with the optimized `release`
+    /// profile into `PROJECT_ROOT/target/release` instead. The `dev` and
+    /// `release` profiles can be configured with the manifest `[profile.dev]`
+    /// and `[profile.release]` tables.
+    ///
+    /// With `--target`, the project is cross-compiled for the given target
+    /// triple into `PROJECT_ROOT/target/TRIPLE/PROFILE`, where `PROFILE` is
+    /// `debug` or `release`. The compilers default to `TRIPLE-g++` and
+    /// `TRIPLE-gcc`, and can be configured, along with a sysroot and extra
+    /// flags, with the manifest `[target.TRIPLE]` table.
+    ///
+    /// With `--sanitize`, such as `--sanitize address,undefined`, the project
+    /// is instrumented with the given sanitizers and built into
+    /// `PROJECT_ROOT/target/sanitize-SANITIZERS` instead. The `thread`
+    /// sanitizer can't be combined with `address` or `leak`.
+    ///
+    /// A `PROJECT_ROOT/build.cpp` build script, or the one of the manifest
+    /// `[build] script`, is compiled and run before the project is built. It
+    /// configures the build, such as with generated sources, by printing
+    /// `cppargo:KEY=VALUE` lines.
+    ///
+    /// The manifest `[build] precompiled-header` is compiled once per build
+    /// directory, such as `PROJECT_ROOT/target/pch`, and again only once it,
+    /// a header it includes, or the compiler flags change. It's then included
+    /// in every C++ source file, except for C++20 module units, which can
+    /// include it in their global module fragment. GCC can't use precompiled
+    /// headers along with modules, so it only includes the header then.
+    ///
+    /// C++20 module interface units, `.cppm` or `.ixx` files, are compiled
+    /// before the source files importing them, with their compiled
+    /// interfaces stored in the `modules/` directory of the build, such as
+    /// `PROJECT_ROOT/target/modules`.
+    ///
+    /// Compilers are run through the `CPPARGO_COMPILER_LAUNCHER` command, or
+    /// the manifest `[build] compiler-launcher`, such as `ccache`. Without
+    /// one, object files are cached in `~/.cppargo/cache`, or
+    /// `$CPPARGO_HOME/cache`, by a hash of their preprocessed source and
+    /// compiler flags, and reused whenever the same source is compiled again.
+    /// The cache is disabled with `CPPARGO_CACHE=off`, and trimmed to
+    /// `CPPARGO_CACHE_SIZE` MiB, or 1 GiB, after every build.
+    ///
+    /// Inside a workspace, whose root manifest has a `[workspace]` table
+    /// with the `members` globs, such as `["libs/*", "apps/*"]`, every
+    /// member is built into the `target/` directory at the workspace root.
+    /// Only the member containing the current directory is built from
+    /// inside it, and only the member `NAME` with `-p NAME`. Members without
+    /// `src/main.cpp` are libraries, whose sources are only compiled.
     #[command(visible_alias = "b")]
-    Build,
+    Build {
+        /// Build only the workspace member `<NAME>`.
+        #[arg(short, long, value_name = "NAME")]
+        package: Option<String>,
+        /// Build with the optimized `release` profile, into
+        /// `PROJECT_ROOT/target/release`.
+        #[arg(long)]
+        release: bool,
+        /// Cross-compile for the target triple `<TRIPLE>`, such as
+        /// `aarch64-linux-gnu`.
+        #[arg(long, value_name = "TRIPLE")]
+        target: Option<String>,
+        /// Also build every example program in `PROJECT_ROOT/examples`.
+        #[arg(long)]
+        examples: bool,
+        /// Write a `compile_commands.json` at the project root.
+        #[arg(long)]
+        compile_commands: bool,
+        /// Fail the build on any compiler warning, regardless of the
+        /// manifest `[lints]` table.
+        #[arg(long)]
+        deny_warnings: bool,
+        /// Instrument the build with the given sanitizers, placing its
+        /// outputs in a separate `target/sanitize-*` directory.
+        #[arg(long, value_enum, value_delimiter = ',', value_name = "SANITIZERS")]
+        sanitize: Vec<Sanitizer>,
+        /// Report progress as human readable text, or as newline-delimited
+        /// JSON events on stdout.
+        #[arg(long, value_enum, default_value_t)]
+        message_format: MessageFormat,
+        /// Also write the compiler diagnostics as a SARIF log at `<PATH>`.
+        #[arg(long, value_name = "PATH")]
+        sarif: Option<PathBuf>,
+    },
+    /// Check a project for errors.
+    ///
+    /// Compile the source files of the project like the `build` subcommand
+    /// (see `cppargo help build`), into the same object files, but without
+    /// linking any binary. Library projects, without `src/main.cpp`, can be
+    /// checked on their own.
+    #[command(visible_alias = "c")]
+    Check {
+        /// Check only the workspace member `<NAME>`.
+        #[arg(short, long, value_name = "NAME")]
+        package: Option<String>,
+        /// Check with the optimized `release` profile, into
+        /// `PROJECT_ROOT/target/release`.
+        #[arg(long)]
+        release: bool,
+        /// Cross-compile for the target triple `<TRIPLE>`, such as
+        /// `aarch64-linux-gnu`.
+        #[arg(long, value_name = "TRIPLE")]
+        target: Option<String>,
+        /// Fail the check on any compiler warning, regardless of the
+        /// manifest `[lints]` table.
+        #[arg(long)]
+        deny_warnings: bool,
+        /// Report progress as human readable text, or as newline-delimited
+        /// JSON events on stdout.
+        #[arg(long, value_enum, default_value_t)]
+        message_format: MessageFormat,
+    },
     /// Run a project.
     ///
     /// Compile the project by using the same functionality as the `build`
     /// subcommand (see `cppargo help build`), and then excecute the compiled
     /// binary `PROJECT_ROOT/target/PROJECT_NAME` from the current directory.
+    ///
+    /// The binary can be run through a wrapper such as `valgrind`, `gdb` or
+    /// `strace`, given with `--runner` or as the `runner` of the manifest
+    /// `[run]` table. Environment variables for the binary are set with the
+    /// manifest `[run.env]` table.
+    ///
+    /// Binaries cross-compiled with `--target` for a foreign platform are
+    /// only run through `--runner` or the `runner` of the manifest
+    /// `[target.TRIPLE]` table, such as `qemu-aarch64`, never through the one
+    /// of the `[run]` table.
+    ///
+    /// Inside a workspace, the member to run is the one containing the
+    /// current directory, or the one selected with `-p NAME`.
     #[command(visible_alias = "r")]
-    Run,
+    Run {
+        /// Run the workspace member `<NAME>`.
+        #[arg(short, long, value_name = "NAME")]
+        package: Option<String>,
+        /// Build with the optimized `release` profile, into
+        /// `PROJECT_ROOT/target/release`.
+        #[arg(long)]
+        release: bool,
+        /// Cross-compile for the target triple `<TRIPLE>`, such as
+        /// `aarch64-linux-gnu`.
+        #[arg(long, value_name = "TRIPLE")]
+        target: Option<String>,
+        /// Run the example program `PROJECT_ROOT/examples/<NAME>.cpp` instead
+        /// of the project binary.
+        #[arg(long, value_name = "NAME")]
+        example: Option<String>,
+        /// Run the binary through `<COMMAND>`, such as `"valgrind
+        /// --leak-check=full"`, instead of the manifest `runner`.
+        #[arg(long, value_name = "COMMAND")]
+        runner: Option<String>,
+        /// Fail the build on any compiler warning, regardless of the
+        /// manifest `[lints]` table.
+        #[arg(long)]
+        deny_warnings: bool,
+        /// Instrument the build with the given sanitizers, placing its
+        /// outputs in a separate `target/sanitize-*` directory.
+        #[arg(long, value_enum, value_delimiter = ',', value_name = "SANITIZERS")]
+        sanitize: Vec<Sanitizer>,
+        /// Report build progress as human readable text, or as
+        /// newline-delimited JSON events on stdout.
+        #[arg(long, value_enum, default_value_t)]
+        message_format: MessageFormat,
+    },
+    /// Remove the build files of a project.
+    ///
+    /// Removes the `PROJECT_ROOT/target` directory, with every build of the
+    /// project inside it. Inside a workspace, the `target/` directory at its
+    /// root, which is shared by every member, is removed instead.
+    ///
+    /// With `-p NAME`, only the files of the workspace member `NAME` are
+    /// removed from every build inside the shared `target/` directory: its
+    /// `obj/NAME`, `build/NAME`, `pch/NAME` and `modules/NAME` directories,
+    /// its `NAME` binary or `libNAME.a` archive, the binaries of its examples
+    /// and benchmarks, and its benchmark baselines.
+    Clean {
+        /// Clean only the workspace member `<NAME>`.
+        #[arg(short, long, value_name = "NAME")]
+        package: Option<String>,
+    },
+    /// Measure the line coverage of a project.
+    ///
+    /// Compile the project like the `build` subcommand, but instrumented with
+    /// `--coverage` and into `PROJECT_ROOT/target/coverage`, then run the
+    /// compiled binary from the current directory, through the runner and
+    /// with the environment variables of the manifest `[run]` table, like the
+    /// `run` subcommand. Once it exits, `gcov` (or
+    /// `llvm-cov gcov` for Clang) is used to print a summary of the line
+    /// coverage of every project source file, and to write an lcov
+    /// `PROJECT_ROOT/target/coverage/lcov.info` tracefile and an HTML report
+    /// at `PROJECT_ROOT/target/coverage/html/index.html`.
+    ///
+    /// Only the `src/main.cpp` binary is run, so library projects without one
+    /// are not supported.
+    Coverage {
+        /// Measure the coverage of the workspace member `<NAME>`.
+        #[arg(short, long, value_name = "NAME")]
+        package: Option<String>,
+        /// Fail if the total line coverage is under `<PERCENT>`.
+        #[arg(long, value_name = "PERCENT")]
+        fail_under: Option<f64>,
+    },
+    /// Benchmark a project.
+    ///
+    /// Every C++ or C source file in `PROJECT_ROOT/benches` is a benchmark
+    /// program, which is compiled with the `release` profile and linked along
+    /// with every project source file except `src/main.cpp` into
+    /// `PROJECT_ROOT/target/release/benches/NAME`.
+    ///
+    /// Each benchmark is then run from the current directory once to warm up,
+    /// and then timed over several runs. The median run time is printed along
+    /// with its change against the previous run, which is saved as the
+    /// baseline in `PROJECT_ROOT/target/bench/baseline.json`.
+    Bench {
+        /// Benchmark only the workspace member `<NAME>`.
+        #[arg(short, long, value_name = "NAME")]
+        package: Option<String>,
+    },
+    /// Install a project.
+    ///
+    /// The project is built with the `release` profile, and its binary is
+    /// copied into `ROOT/bin`, along with its public headers, every file
+    /// inside `PROJECT_ROOT/include`, into `ROOT/include`. `ROOT` defaults
+    /// to `~/.cppargo`, or `$CPPARGO_HOME` if set. Libraries, without
+    /// `src/main.cpp`, have their objects archived into a `libNAME.a` static
+    /// library instead, which is copied into `ROOT/lib`.
+    ///
+    /// Inside a workspace, every member is installed, or only the one
+    /// selected with `-p NAME`. Every member is checked before any file is
+    /// copied.
+    ///
+    /// Installed files are recorded in `ROOT/.installed.json`, so that they
+    /// can be removed with `cppargo uninstall`. Installing a project again
+    /// replaces its previously installed files, but files installed by
+    /// another project are never overwritten, and other existing files,
+    /// such as those of another package manager, only with `--force`.
+    Install {
+        /// Install only the workspace member `<NAME>`.
+        #[arg(short, long, value_name = "NAME")]
+        package: Option<String>,
+        /// Install into `<ROOT>` instead of `~/.cppargo`.
+        #[arg(long)]
+        root: Option<PathBuf>,
+        /// Overwrite files in `<ROOT>` which weren't installed by `cppargo`.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Uninstall a project.
+    ///
+    /// Removes every file installed by `cppargo install` for the project
+    /// `<NAME>`, along with the directories left empty.
+    Uninstall {
+        /// Name of the installed project.
+        name: String,
+        /// Uninstall from `<ROOT>` instead of `~/.cppargo`.
+        #[arg(long)]
+        root: Option<PathBuf>,
+    },
+    /// Package the sources of a project.
+    ///
+    /// The manifest, every file inside `PROJECT_ROOT/src` and
+    /// `PROJECT_ROOT/include`, the build script, the precompiled header and
+    /// the files matching the manifest `[package] include` globs are archived
+    /// into `PROJECT_ROOT/target/package/NAME-VERSION.tar.gz`, inside a
+    /// `NAME-VERSION/` directory. The `target/` directory and version control
+    /// data are never archived. The `version` is the one of the manifest
+    /// `[project]` table, which is required.
+    ///
+    /// Archives are reproducible: packaging the same files again gives the
+    /// same archive, regardless of their modification times or owners.
+    ///
+    /// The archive is then verified by unpacking it into a temporary
+    /// directory and building the project there.
+    Package {
+        /// Don't verify that the archive builds.
+        #[arg(long)]
+        no_verify: bool,
+    },
 }
@@ -0,0 +1,488 @@
+use crate::{build, run, Context};
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    fs,
+    path::{Component, Path, PathBuf},
+    process::Command,
+};
+
+/// Execution count of every executable line of a source file, by line number.
+type LineCounts = BTreeMap<u32, u64>;
+
+/// Run the project binary of the `artifacts` built with `--coverage` from
+/// `current_dir`, through their runner and with their environment variables
+/// like `cppargo run`, and report the line coverage of every project source file
+/// it executed.
+///
+/// A summary is printed, and `lcov.info` and `html/` reports are written next
+/// to the binary. Fails if the total line coverage is under `fail_under`
+/// percent.
+pub fn main(
+    current_dir: &Path,
+    artifacts: &build::Artifacts,
+    fail_under: Option<f64>,
+) -> anyhow::Result<()> {
+    let project_root = &artifacts.project_root;
+    let Some(project_binary) = &artifacts.binary else {
+        anyhow::bail!(format!(
+            "Project {} is a library without a `src/main.cpp` binary to run! \
+            Measuring the coverage of library projects is not supported.",
+            project_root.display()
+        ));
+    };
+    let Some(coverage_dir) = project_binary.parent() else {
+        anyhow::bail!(format!(
+            "Project binary {} has no parent directory!",
+            project_binary.display()
+        ));
+    };
+
+    // Counts are accumulated across runs, so drop the ones of previous runs.
+    let objects_dir = &artifacts.objects_dir;
+    for data_file in find_data_files(objects_dir)? {
+        fs::remove_file(&data_file)
+            .with_context(|| format!("Failed to remove coverage data {}!", data_file.display()))?;
+    }
+
+    let status = run::command(project_binary, &artifacts.runner)?
+        .current_dir(current_dir)
+        .status()
+        .with_context(|| {
+            format!(
+                "Couldn't excecute project file {}",
+                project_binary.display()
+            )
+        })?;
+    anyhow::ensure!(
+        status.success(),
+        format!("Project excecutable exited with {status}!")
+    );
+
+    let mut coverage = BTreeMap::new();
+    for data_file in find_data_files(objects_dir)? {
+        let output = gcov_command(&data_file)
+            .current_dir(project_root)
+            .output()
+            .with_context(|| "Couldn't start `gcov`!")?;
+        anyhow::ensure!(
+            output.status.success(),
+            format!(
+                "Failed to process coverage data {}!\n{}",
+                data_file.display(),
+                String::from_utf8_lossy(&output.stderr).trim_end()
+            )
+        );
+
+        merge(
+            &mut coverage,
+            parse_gcov(&String::from_utf8_lossy(&output.stdout), project_root),
+        );
+    }
+
+    print!("{}", summary(&coverage));
+
+    let lcov_path = coverage_dir.join("lcov.info");
+    fs::write(&lcov_path, lcov(&coverage, project_root))
+        .with_context(|| format!("Failed to write lcov report {}!", lcov_path.display()))?;
+    let html_dir = coverage_dir.join("html");
+    write_html(&coverage, project_root, &html_dir)
+        .with_context(|| format!("Failed to write HTML report {}!", html_dir.display()))?;
+    println!(
+        "Coverage reports written to {} and {}.",
+        lcov_path.display(),
+        html_dir.join("index.html").display()
+    );
+
+    if let Some(fail_under) = fail_under {
+        let (lines, covered) = totals(coverage.values());
+        let percentage = percentage(lines, covered);
+        anyhow::ensure!(
+            percentage >= fail_under,
+            format!("Line coverage {percentage:.2}% is under the required {fail_under:.2}%!")
+        );
+    }
+
+    Ok(())
+}
+
+fn find_data_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut data_files = Vec::new();
+    if !dir.is_dir() {
+        return Ok(data_files);
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            data_files.extend(find_data_files(&path)?);
+        } else if path.extension().is_some_and(|ext| ext == "gcda") {
+            data_files.push(path);
+        }
+    }
+    data_files.sort();
+
+    Ok(data_files)
+}
+
+/// `gcov`, or `llvm-cov gcov` if the compiler is Clang, printing the annotated
+/// sources of `data_file` on stdout.
+fn gcov_command(data_file: &Path) -> Command {
+    let mut gcov = if build::is_clang(build::CPP_COMPILER) {
+        let mut gcov = Command::new("llvm-cov");
+        gcov.arg("gcov");
+        gcov
+    } else {
+        Command::new("gcov")
+    };
+    gcov.arg("--stdout").arg(data_file);
+
+    gcov
+}
+
+/// Parse the annotated sources printed by `gcov --stdout`, keeping only the
+/// files inside `project_root`.
+fn parse_gcov(output: &str, project_root: &Path) -> BTreeMap<PathBuf, LineCounts> {
+    let mut coverage: BTreeMap<PathBuf, LineCounts> = BTreeMap::new();
+    let mut current_file = None;
+
+    for line in output.lines() {
+        let mut fields = line.splitn(3, ':');
+        let (Some(count), Some(line_number), Some(text)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let Ok(line_number) = line_number.trim().parse::<u32>() else {
+            continue;
+        };
+
+        if line_number == 0 {
+            if let Some(source) = text.strip_prefix("Source:") {
+                current_file = project_file(Path::new(source), project_root);
+            }
+            continue;
+        }
+
+        let Some(file) = &current_file else {
+            continue;
+        };
+        let count = match count.trim().trim_end_matches('*') {
+            "-" => continue,
+            "#####" | "=====" => 0,
+            count => match count.parse() {
+                Ok(count) => count,
+                Err(_) => continue,
+            },
+        };
+
+        // Template instantiations repeat the lines of their template after
+        // its total count, so only the first count of each line is kept.
+        coverage
+            .entry(file.clone())
+            .or_default()
+            .entry(line_number)
+            .or_insert(count);
+    }
+
+    coverage
+}
+
+/// Path of `source` relative to `project_root`, if it's a project file.
+///
+/// `..` components are resolved, so that the path never leads outside of the
+/// directories the reports are written to.
+fn project_file(source: &Path, project_root: &Path) -> Option<PathBuf> {
+    let mut file = PathBuf::new();
+    for component in source.components() {
+        match component {
+            Component::ParentDir => {
+                if !file.pop() {
+                    return None;
+                }
+            }
+            Component::CurDir => {}
+            _ => file.push(component),
+        }
+    }
+
+    if file.is_relative() {
+        Some(file)
+    } else {
+        file.strip_prefix(project_root).ok().map(Path::to_path_buf)
+    }
+}
+
+/// Add the counts of `other` to `coverage`, such as the counts of a header
+/// included by several source files.
+fn merge(coverage: &mut BTreeMap<PathBuf, LineCounts>, other: BTreeMap<PathBuf, LineCounts>) {
+    for (file, line_counts) in other {
+        let file_counts = coverage.entry(file).or_default();
+        for (line_number, count) in line_counts {
+            *file_counts.entry(line_number).or_default() += count;
+        }
+    }
+}
+
+/// Number of executable and covered lines.
+fn totals<'a>(line_counts: impl IntoIterator<Item = &'a LineCounts>) -> (usize, usize) {
+    line_counts
+        .into_iter()
+        .fold((0, 0), |(lines, covered), line_counts| {
+            (
+                lines + line_counts.len(),
+                covered + line_counts.values().filter(|&&count| count > 0).count(),
+            )
+        })
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn percentage(lines: usize, covered: usize) -> f64 {
+    if lines == 0 {
+        100.0
+    } else {
+        covered as f64 * 100.0 / lines as f64
+    }
+}
+
+fn summary(coverage: &BTreeMap<PathBuf, LineCounts>) -> String {
+    let width = coverage
+        .keys()
+        .map(|file| file.to_string_lossy().len())
+        .chain(["Filename".len(), "TOTAL".len()])
+        .max()
+        .unwrap_or_default();
+
+    let mut summary = format!(
+        "{:<width$}  {:>7}  {:>7}  {:>8}\n",
+        "Filename", "Lines", "Covered", "Coverage"
+    );
+    let mut row = |name: &str, (lines, covered): (usize, usize)| {
+        let percentage = percentage(lines, covered);
+        let _ = writeln!(
+            summary,
+            "{name:<width$}  {lines:>7}  {covered:>7}  {percentage:>7.2}%"
+        );
+    };
+    for (file, line_counts) in coverage {
+        row(&file.to_string_lossy(), totals([line_counts]));
+    }
+    row("TOTAL", totals(coverage.values()));
+
+    summary
+}
+
+/// An lcov tracefile, as read by `genhtml` and most CI coverage services.
+fn lcov(coverage: &BTreeMap<PathBuf, LineCounts>, project_root: &Path) -> String {
+    let mut lcov = String::new();
+    for (file, line_counts) in coverage {
+        let (lines, covered) = totals([line_counts]);
+        let _ = writeln!(lcov, "TN:");
+        let _ = writeln!(lcov, "SF:{}", project_root.join(file).display());
+        for (line_number, count) in line_counts {
+            let _ = writeln!(lcov, "DA:{line_number},{count}");
+        }
+        let _ = writeln!(lcov, "LF:{lines}");
+        let _ = writeln!(lcov, "LH:{covered}");
+        let _ = writeln!(lcov, "end_of_record");
+    }
+
+    lcov
+}
+
+/// Write an `index.html` summary to `html_dir`, linking to a page for every
+/// source file with its lines annotated with their execution counts.
+fn write_html(
+    coverage: &BTreeMap<PathBuf, LineCounts>,
+    project_root: &Path,
+    html_dir: &Path,
+) -> anyhow::Result<()> {
+    if html_dir.exists() {
+        fs::remove_dir_all(html_dir)?;
+    }
+    fs::create_dir_all(html_dir)?;
+
+    let mut rows = String::new();
+    for (file, line_counts) in coverage {
+        let (lines, covered) = totals([line_counts]);
+        let name = escape_html(&file.to_string_lossy());
+        let _ = writeln!(
+            rows,
+            "<tr><td><a href=\"{name}.html\">{name}</a></td><td>{lines}</td><td>{covered}</td><td>{:.2}%</td></tr>",
+            percentage(lines, covered)
+        );
+
+        let source = fs::read_to_string(project_root.join(file)).unwrap_or_default();
+        let mut page_path = html_dir.join(file).into_os_string();
+        page_path.push(".html");
+        let page_path = PathBuf::from(page_path);
+        if let Some(page_dir) = page_path.parent() {
+            fs::create_dir_all(page_dir)?;
+        }
+        fs::write(&page_path, html_file_page(&name, &source, line_counts))?;
+    }
+
+    let (lines, covered) = totals(coverage.values());
+    let index = format!(
+        concat!(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Coverage</title></head>\n",
+            "<body>\n<h1>Line coverage</h1>\n<table>\n",
+            "<tr><th>Filename</th><th>Lines</th><th>Covered</th><th>Coverage</th></tr>\n",
+            "{}<tr><th>TOTAL</th><th>{}</th><th>{}</th><th>{:.2}%</th></tr>\n",
+            "</table>\n</body>\n</html>\n"
+        ),
+        rows,
+        lines,
+        covered,
+        percentage(lines, covered)
+    );
+    fs::write(html_dir.join("index.html"), index)?;
+
+    Ok(())
+}
+
+fn html_file_page(name: &str, source: &str, line_counts: &LineCounts) -> String {
+    let mut lines = String::new();
+    for (line_number, text) in (1..).zip(source.lines()) {
+        let (count, style) = match line_counts.get(&line_number) {
+            Some(0) => ("0".to_string(), " style=\"background:#fdd\""),
+            Some(count) => (count.to_string(), " style=\"background:#dfd\""),
+            None => (String::new(), ""),
+        };
+        let _ = writeln!(
+            lines,
+            "<tr{style}><td>{line_number}</td><td>{count}</td><td><pre>{}</pre></td></tr>",
+            escape_html(text)
+        );
+    }
+
+    format!(
+        concat!(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{name}</title></head>\n",
+            "<body>\n<h1>{name}</h1>\n<table>\n{lines}</table>\n</body>\n</html>\n"
+        ),
+        name = name,
+        lines = lines
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GCOV_OUTPUT: &str = concat!(
+        "        -:    0:Source:src/main.cpp\n",
+        "        -:    0:Runs:1\n",
+        "        -:    1:#include \"lib.hpp\"\n",
+        "        1:    2:int main(int argc, char**) {\n",
+        "       1*:    3:  if (argc > 5) {\n",
+        "    #####:    4:    return 1;\n",
+        "        -:    5:  }\n",
+        "        1:    6:  return twice(0);\n",
+        "        -:    7:}\n",
+        "        -:    0:Source:src/lib.hpp\n",
+        "        1:    1:template <typename T> T twice(T x) {\n",
+        "------------------\n",
+        "_Z5twiceIiET_S0_:\n",
+        "        1:    1:template <typename T> T twice(T x) {\n",
+        "------------------\n",
+        "        -:    0:Source:/usr/include/c++/12/iostream\n",
+        "        1:   74:  static ios_base::Init __ioinit;\n",
+    );
+
+    #[test]
+    fn proper_parse_gcov() -> anyhow::Result<()> {
+        let coverage = parse_gcov(GCOV_OUTPUT, Path::new("/project"));
+        let expected = BTreeMap::from([
+            (PathBuf::from("src/lib.hpp"), LineCounts::from([(1, 1)])),
+            (
+                PathBuf::from("src/main.cpp"),
+                LineCounts::from([(2, 1), (3, 1), (4, 0), (6, 1)]),
+            ),
+        ]);
+
+        anyhow::ensure!(
+            coverage == expected,
+            format!("Got unexpected coverage {coverage:?}!")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_parent_dirs_of_project_files() -> anyhow::Result<()> {
+        let project_root = Path::new("/project");
+        for (source, expected) in [
+            ("src/../include/lib.hpp", Some("include/lib.hpp")),
+            ("/project/target/../src/main.cpp", Some("src/main.cpp")),
+            ("../core/src/core.cpp", None),
+            ("src/../../outside.cpp", None),
+            ("/project/../outside.cpp", None),
+        ] {
+            let file = project_file(Path::new(source), project_root);
+            anyhow::ensure!(
+                file.as_deref() == expected.map(Path::new),
+                format!("Got unexpected project file {file:?} for {source}!")
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn merge_counts_of_shared_files() -> anyhow::Result<()> {
+        let mut coverage = parse_gcov(GCOV_OUTPUT, Path::new("/project"));
+        merge(
+            &mut coverage,
+            parse_gcov(GCOV_OUTPUT, Path::new("/project")),
+        );
+
+        anyhow::ensure!(
+            coverage[Path::new("src/main.cpp")]
+                == LineCounts::from([(2, 2), (3, 2), (4, 0), (6, 2)]),
+            format!("Got unexpected coverage {coverage:?}!")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn proper_lcov() -> anyhow::Result<()> {
+        let coverage = parse_gcov(GCOV_OUTPUT, Path::new("/project"));
+        let lcov = lcov(&coverage, Path::new("/project"));
+        let expected = concat!(
+            "TN:\nSF:/project/src/lib.hpp\nDA:1,1\nLF:1\nLH:1\nend_of_record\n",
+            "TN:\nSF:/project/src/main.cpp\nDA:2,1\nDA:3,1\nDA:4,0\nDA:6,1\nLF:4\nLH:3\nend_of_record\n",
+        );
+
+        anyhow::ensure!(lcov == expected, format!("Got unexpected lcov {lcov}!"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn proper_summary() -> anyhow::Result<()> {
+        let coverage = parse_gcov(GCOV_OUTPUT, Path::new("/project"));
+        let summary = summary(&coverage);
+        let expected = concat!(
+            "Filename        Lines  Covered  Coverage\n",
+            "src/lib.hpp         1        1   100.00%\n",
+            "src/main.cpp        4        3    75.00%\n",
+            "TOTAL               5        4    80.00%\n",
+        );
+
+        anyhow::ensure!(
+            summary == expected,
+            format!("Got unexpected summary:\n{summary}")
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,714 @@
+use std::{
+    fmt::{self, Display},
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Note => "note",
+        }
+    }
+}
+
+/// A location in a source file, with 1-based line and column numbers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Location {
+    pub file: PathBuf,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+/// A suggested replacement of the text between `start` and `end`, as printed
+/// by `-fdiagnostics-parseable-fixits`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FixIt {
+    pub file: PathBuf,
+    pub start: (u32, u32),
+    pub end: (u32, u32),
+    pub replacement: String,
+}
+
+/// A diagnostic printed by GCC or Clang, along with the lines giving its
+/// context, its source excerpt, and the notes and fix-its following it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Where the diagnostic points to. Diagnostics from the compiler driver or
+    /// linker, such as `collect2: error: ...`, have none.
+    pub location: Option<Location>,
+    pub message: String,
+    /// Lines printed before the diagnostic, such as `file: In instantiation
+    /// of ...:`, `In file included from file:1:` or `file:7:13:   required
+    /// from here`.
+    pub context: Vec<String>,
+    /// Lines printed after the diagnostic, quoting the source it points to
+    /// (`    3 |   code`), and marking it (`      |   ^~~`).
+    pub excerpt: Vec<String>,
+    pub notes: Vec<Diagnostic>,
+    pub fixits: Vec<FixIt>,
+}
+
+/// A part of everything a compiler printed.
+#[derive(Debug, PartialEq)]
+pub enum Output {
+    Diagnostic(Diagnostic),
+    /// Lines that couldn't be parsed, such as linker errors.
+    Other(Vec<String>),
+}
+
+/// Everything a compiler printed, split into the diagnostics that could be
+/// parsed, and the remaining lines that couldn't, in the order they were
+/// printed.
+#[derive(Debug, Default, PartialEq)]
+pub struct CompilerOutput {
+    pub parts: Vec<Output>,
+}
+
+impl CompilerOutput {
+    pub fn diagnostics_mut(&mut self) -> impl Iterator<Item = &mut Diagnostic> {
+        self.parts.iter_mut().filter_map(|part| match part {
+            Output::Diagnostic(diagnostic) => Some(diagnostic),
+            Output::Other(_) => None,
+        })
+    }
+
+    /// The diagnostic printed last, if nothing else was printed after it.
+    fn last_diagnostic(&mut self) -> Option<&mut Diagnostic> {
+        match self.parts.last_mut() {
+            Some(Output::Diagnostic(diagnostic)) => Some(diagnostic),
+            _ => None,
+        }
+    }
+
+    fn push_other(&mut self, lines: impl IntoIterator<Item = String>) {
+        match self.parts.last_mut() {
+            Some(Output::Other(other_lines)) => other_lines.extend(lines),
+            _ => self.parts.push(Output::Other(lines.into_iter().collect())),
+        }
+    }
+}
+
+/// Parse the output of GCC or Clang.
+///
+/// Context lines, such as `In function 'int main()':`, are attached to the
+/// diagnostic following them, and source excerpts to the diagnostic, or note,
+/// preceding them, so that they are re-rendered along with it.
+pub fn parse(output: &str) -> CompilerOutput {
+    let mut parsed = CompilerOutput::default();
+    let mut context = Vec::new();
+
+    for line in output.lines() {
+        if let Some(fixit) = parse_fixit(line) {
+            if let Some(diagnostic) = parsed.last_diagnostic() {
+                diagnostic.fixits.push(fixit);
+            }
+        } else if let Some(diagnostic) = parsed
+            .last_diagnostic()
+            .filter(|_| context.is_empty() && is_excerpt(line))
+        {
+            // Excerpts are checked first, as the source they quote may contain
+            // a marker such as `": error: "` too.
+            match diagnostic.notes.last_mut() {
+                Some(note) => note.excerpt.push(line.to_string()),
+                None => diagnostic.excerpt.push(line.to_string()),
+            }
+        } else if let Some(mut diagnostic) = parse_diagnostic(line) {
+            diagnostic.context = std::mem::take(&mut context);
+            match (diagnostic.severity, parsed.last_diagnostic()) {
+                (Severity::Note, Some(parent)) => parent.notes.push(diagnostic),
+                _ => parsed.parts.push(Output::Diagnostic(diagnostic)),
+            }
+        } else if is_context(line) {
+            context.push(line.to_string());
+        } else if !line.trim().is_empty() {
+            let context = std::mem::take(&mut context);
+            parsed.push_other(context.into_iter().chain([line.to_string()]));
+        }
+    }
+    // Context without a diagnostic following it is kept as it is.
+    if !context.is_empty() {
+        parsed.push_other(context);
+    }
+
+    parsed
+}
+
+fn parse_diagnostic(line: &str) -> Option<Diagnostic> {
+    // The message itself may quote another marker, such as a `#warning` of
+    // "error: ...", so the earliest one is the severity of the diagnostic.
+    let (index, marker, severity) = [
+        (": fatal error: ", Severity::Error),
+        (": error: ", Severity::Error),
+        (": warning: ", Severity::Warning),
+        (": note: ", Severity::Note),
+    ]
+    .into_iter()
+    .filter_map(|(marker, severity)| Some((line.find(marker)?, marker, severity)))
+    .min_by_key(|(index, ..)| *index)?;
+
+    Some(Diagnostic {
+        severity,
+        location: parse_location(&line[..index]),
+        message: line[index + marker.len()..].to_string(),
+        context: Vec::new(),
+        excerpt: Vec::new(),
+        notes: Vec::new(),
+        fixits: Vec::new(),
+    })
+}
+
+/// Parse `file:line:column` or `file:line`. Anything else, such as a program
+/// like `collect2` or `/usr/bin/ld`, is not a location.
+fn parse_location(location: &str) -> Option<Location> {
+    let mut parts = location.rsplitn(3, ':');
+    let last = parts.next()?;
+    let middle = parts.next();
+    let first = parts.next();
+
+    match (first, middle, last.parse::<u32>()) {
+        (Some(file), Some(line), Ok(column)) if line.parse::<u32>().is_ok() => Some(Location {
+            file: PathBuf::from(file),
+            line: line.parse().ok(),
+            column: Some(column),
+        }),
+        (_, Some(_), Ok(line)) => {
+            let (file, _) = location.rsplit_once(':')?;
+            Some(Location {
+                file: PathBuf::from(file),
+                line: Some(line),
+                column: None,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Parse `fix-it:"file":{line:column-line:column}:"replacement"`.
+fn parse_fixit(line: &str) -> Option<FixIt> {
+    let rest = line.strip_prefix("fix-it:\"")?;
+    let (file, rest) = rest.split_once("\":{")?;
+    let (range, rest) = rest.split_once("}:\"")?;
+    let replacement = rest.strip_suffix('"')?;
+    let (start, end) = range.split_once('-')?;
+
+    let parse_position = |position: &str| -> Option<(u32, u32)> {
+        let (line, column) = position.split_once(':')?;
+        Some((line.parse().ok()?, column.parse().ok()?))
+    };
+
+    Some(FixIt {
+        file: PathBuf::from(file),
+        start: parse_position(start)?,
+        end: parse_position(end)?,
+        replacement: unescape(replacement),
+    })
+}
+
+fn unescape(escaped: &str) -> String {
+    let mut unescaped = String::with_capacity(escaped.len());
+    let mut chars = escaped.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+/// Whether `line` is part of a source excerpt, such as `    3 |   code` or
+/// `      |   ^~~`.
+fn is_excerpt(line: &str) -> bool {
+    line.starts_with(' ')
+        && line
+            .trim_start()
+            .trim_start_matches(|c: char| c.is_ascii_digit() || c == '+')
+            .trim_start()
+            .starts_with('|')
+}
+
+/// Whether `line` gives the context of the diagnostic following it, such as
+/// `file: In function 'int main()':`, `In file included from file:1:` or
+/// `file:7:13:   required from here`.
+fn is_context(line: &str) -> bool {
+    let trimmed = line.trim_start();
+
+    trimmed.starts_with("In file included from ")
+        || (line.starts_with(' ') && trimmed.starts_with("from "))
+        || (line.ends_with(':') && (line.contains(": In ") || line.contains(": At ")))
+        || line.contains(":   required from ")
+        || line.contains(":   recursively required from ")
+        || line.contains(":   required by ")
+        || line.ends_with(":   required from here")
+}
+
+impl Diagnostic {
+    /// Make every path of the diagnostic relative to `project_root`, if it is
+    /// inside of it.
+    pub fn relativize(&mut self, project_root: &Path) {
+        let relativize = |path: &mut PathBuf| {
+            if let Ok(relative) = path.strip_prefix(project_root) {
+                *path = relative.to_path_buf();
+            }
+        };
+
+        if let Some(location) = &mut self.location {
+            relativize(&mut location.file);
+        }
+        let prefix = format!("{}/", project_root.display());
+        for line in &mut self.context {
+            *line = line.replace(&prefix, "");
+        }
+        for fixit in &mut self.fixits {
+            relativize(&mut fixit.file);
+        }
+        for note in &mut self.notes {
+            note.relativize(project_root);
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "severity": self.severity.as_str(),
+            "file": self.location.as_ref().map(|l| l.file.to_string_lossy()),
+            "line": self.location.as_ref().and_then(|l| l.line),
+            "column": self.location.as_ref().and_then(|l| l.column),
+            "message": self.message,
+            "notes": self.notes.iter().map(Diagnostic::to_json).collect::<Vec<_>>(),
+            "fixits": self.fixits.iter().map(|fixit| serde_json::json!({
+                "file": fixit.file.to_string_lossy(),
+                "start": { "line": fixit.start.0, "column": fixit.start.1 },
+                "end": { "line": fixit.end.0, "column": fixit.end.1 },
+                "replacement": fixit.replacement,
+            })).collect::<Vec<_>>(),
+        })
+    }
+
+    /// The diagnostic as a SARIF `result` object.
+    fn to_sarif(&self) -> serde_json::Value {
+        let sarif_location = |location: &Location| {
+            let mut region = serde_json::Map::new();
+            if let Some(line) = location.line {
+                region.insert("startLine".to_string(), line.into());
+            }
+            if let Some(column) = location.column {
+                region.insert("startColumn".to_string(), column.into());
+            }
+
+            serde_json::json!({
+                "physicalLocation": {
+                    "artifactLocation": { "uri": location.file.to_string_lossy() },
+                    "region": region,
+                }
+            })
+        };
+
+        let mut result = serde_json::json!({
+            "level": self.severity.as_str(),
+            "message": { "text": self.message },
+            "locations": self.location.iter().map(sarif_location).collect::<Vec<_>>(),
+            "relatedLocations": self
+                .notes
+                .iter()
+                .filter_map(|note| {
+                    let mut location = sarif_location(note.location.as_ref()?);
+                    location["message"] = serde_json::json!({ "text": note.message });
+                    Some(location)
+                })
+                .collect::<Vec<_>>(),
+        });
+
+        if !self.fixits.is_empty() {
+            result["fixes"] = serde_json::json!([{
+                "artifactChanges": self.fixits.iter().map(|fixit| serde_json::json!({
+                    "artifactLocation": { "uri": fixit.file.to_string_lossy() },
+                    "replacements": [{
+                        "deletedRegion": {
+                            "startLine": fixit.start.0,
+                            "startColumn": fixit.start.1,
+                            "endLine": fixit.end.0,
+                            "endColumn": fixit.end.1,
+                        },
+                        "insertedContent": { "text": fixit.replacement },
+                    }],
+                })).collect::<Vec<_>>(),
+            }]);
+        }
+
+        result
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        if let Some(column) = self.column {
+            write!(f, ":{column}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.context {
+            writeln!(f, "{line}")?;
+        }
+        writeln!(f, "{}: {}", self.severity.as_str(), self.message)?;
+        if let Some(location) = &self.location {
+            writeln!(f, "  --> {location}")?;
+        }
+        for line in &self.excerpt {
+            writeln!(f, "{line}")?;
+        }
+        for note in &self.notes {
+            for line in &note.context {
+                writeln!(f, "{line}")?;
+            }
+            match &note.location {
+                Some(location) => writeln!(f, "  = note: {} ({location})", note.message)?,
+                None => writeln!(f, "  = note: {}", note.message)?,
+            }
+            for line in &note.excerpt {
+                writeln!(f, "{line}")?;
+            }
+        }
+        for fixit in &self.fixits {
+            writeln!(
+                f,
+                "  = help: replace {}:{}:{}-{}:{} with `{}`",
+                fixit.file.display(),
+                fixit.start.0,
+                fixit.start.1,
+                fixit.end.0,
+                fixit.end.1,
+                fixit.replacement
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A SARIF 2.1.0 log of `diagnostics`, for code scanning tools.
+pub fn sarif(diagnostics: &[Diagnostic]) -> serde_json::Value {
+    serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_HOMEPAGE"),
+                }
+            },
+            "results": diagnostics.iter().map(Diagnostic::to_sarif).collect::<Vec<_>>(),
+        }]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GCC_OUTPUT: &str = concat!(
+        "src/main.cpp: In function 'int main()':\n",
+        "src/main.cpp:4:12: error: 'undeclared' was not declared in this scope; did you mean 'declared'?\n",
+        "    4 |     return undeclared;\n",
+        "      |            ^~~~~~~~~~\n",
+        "      |            declared\n",
+        "fix-it:\"src/main.cpp\":{4:12-4:22}:\"declared\"\n",
+        "src/main.cpp:3:9: note: 'declared' declared here\n",
+        "    3 |     int declared = 0;\n",
+        "      |         ^~~~~~~~\n",
+        "src/main.cpp:2:5: warning: unused variable 'unused' [-Wunused-variable]\n",
+    );
+
+    const LINKER_OUTPUT: &str = concat!(
+        "/usr/bin/ld: target/obj/src/main.cpp.o: in function `main':\n",
+        "main.cpp:(.text+0x5): undefined reference to `missing()'\n",
+        "/usr/bin/ld: warning: main.o: missing .note.GNU-stack section implies executable stack\n",
+        "collect2: error: ld returned 1 exit status\n",
+    );
+
+    const TEMPLATE_OUTPUT: &str = concat!(
+        "src/main.cpp: In instantiation of 'int f(T) [with T = int]':\n",
+        "src/main.cpp:7:13:   required from here\n",
+        "src/main.cpp:3:14: error: request for member 'size' in 't', which is of non-class type 'int'\n",
+        "    3 |     return t.size();\n",
+        "      |            ~~^~~~\n",
+    );
+
+    fn diagnostics(output: &str) -> Vec<Diagnostic> {
+        parse(output)
+            .parts
+            .into_iter()
+            .filter_map(|part| match part {
+                Output::Diagnostic(diagnostic) => Some(diagnostic),
+                Output::Other(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_gcc_output() -> anyhow::Result<()> {
+        let parsed = parse(GCC_OUTPUT);
+
+        let expected = CompilerOutput {
+            parts: vec![
+                Output::Diagnostic(Diagnostic {
+                    severity: Severity::Error,
+                    location: Some(Location {
+                        file: PathBuf::from("src/main.cpp"),
+                        line: Some(4),
+                        column: Some(12),
+                    }),
+                    message:
+                        "'undeclared' was not declared in this scope; did you mean 'declared'?"
+                            .to_string(),
+                    context: vec!["src/main.cpp: In function 'int main()':".to_string()],
+                    excerpt: vec![
+                        "    4 |     return undeclared;".to_string(),
+                        "      |            ^~~~~~~~~~".to_string(),
+                        "      |            declared".to_string(),
+                    ],
+                    notes: vec![Diagnostic {
+                        severity: Severity::Note,
+                        location: Some(Location {
+                            file: PathBuf::from("src/main.cpp"),
+                            line: Some(3),
+                            column: Some(9),
+                        }),
+                        message: "'declared' declared here".to_string(),
+                        context: Vec::new(),
+                        excerpt: vec![
+                            "    3 |     int declared = 0;".to_string(),
+                            "      |         ^~~~~~~~".to_string(),
+                        ],
+                        notes: Vec::new(),
+                        fixits: Vec::new(),
+                    }],
+                    fixits: vec![FixIt {
+                        file: PathBuf::from("src/main.cpp"),
+                        start: (4, 12),
+                        end: (4, 22),
+                        replacement: "declared".to_string(),
+                    }],
+                }),
+                Output::Diagnostic(Diagnostic {
+                    severity: Severity::Warning,
+                    location: Some(Location {
+                        file: PathBuf::from("src/main.cpp"),
+                        line: Some(2),
+                        column: Some(5),
+                    }),
+                    message: "unused variable 'unused' [-Wunused-variable]".to_string(),
+                    context: Vec::new(),
+                    excerpt: Vec::new(),
+                    notes: Vec::new(),
+                    fixits: Vec::new(),
+                }),
+            ],
+        };
+
+        anyhow::ensure!(
+            parsed == expected,
+            format!("Got unexpected diagnostics:\n{parsed:#?}")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_linker_output() -> anyhow::Result<()> {
+        let parsed = parse(LINKER_OUTPUT);
+
+        // Lines that couldn't be parsed are kept in place.
+        let [Output::Other(lines), Output::Diagnostic(warning), Output::Diagnostic(error)] =
+            &parsed.parts[..]
+        else {
+            anyhow::bail!(format!("Got unexpected output:\n{parsed:#?}"));
+        };
+        anyhow::ensure!(
+            lines.len() == 2
+                && warning.severity == Severity::Warning
+                && warning.location.is_none()
+                && warning.message.starts_with("main.o: missing")
+                && error.location.is_none()
+                && error.message == "ld returned 1 exit status",
+            format!("Got unexpected output:\n{parsed:#?}")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_template_instantiation() -> anyhow::Result<()> {
+        let parsed = diagnostics(TEMPLATE_OUTPUT);
+
+        anyhow::ensure!(
+            parsed.len() == 1
+                && parsed[0].context
+                    == [
+                        "src/main.cpp: In instantiation of 'int f(T) [with T = int]':",
+                        "src/main.cpp:7:13:   required from here",
+                    ]
+                && parsed[0].excerpt.len() == 2,
+            format!("Got unexpected diagnostics:\n{parsed:#?}")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_earliest_severity() -> anyhow::Result<()> {
+        let parsed = diagnostics(
+            "src/main.cpp:1:2: warning: #warning \"deprecated: error: use bar\" [-Wcpp]\n",
+        );
+
+        let expected = vec![Diagnostic {
+            severity: Severity::Warning,
+            location: Some(Location {
+                file: PathBuf::from("src/main.cpp"),
+                line: Some(1),
+                column: Some(2),
+            }),
+            message: "#warning \"deprecated: error: use bar\" [-Wcpp]".to_string(),
+            context: Vec::new(),
+            excerpt: Vec::new(),
+            notes: Vec::new(),
+            fixits: Vec::new(),
+        }];
+
+        anyhow::ensure!(
+            parsed == expected,
+            format!("Got unexpected diagnostics:\n{parsed:#?}")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_excerpt_with_marker() -> anyhow::Result<()> {
+        let parsed = diagnostics(concat!(
+            "src/main.cpp:3:5: warning: format not a string literal [-Wformat-security]\n",
+            "    3 |     fprintf(stderr, \"%s: error: %s\\n\", name, message);\n",
+            "      |     ^~~~~~~\n",
+        ));
+
+        anyhow::ensure!(
+            parsed.len() == 1
+                && parsed[0].severity == Severity::Warning
+                && parsed[0].excerpt.len() == 2,
+            format!("Got unexpected diagnostics:\n{parsed:#?}")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn relativize_paths() -> anyhow::Result<()> {
+        let mut diagnostic = diagnostics(concat!(
+            "/project/src/main.cpp: In function 'int main()':\n",
+            "/project/src/main.cpp:1:1: error: oops\n"
+        ))
+        .remove(0);
+        diagnostic.relativize(Path::new("/project"));
+
+        anyhow::ensure!(
+            diagnostic.location.as_ref().map(ToString::to_string)
+                == Some("src/main.cpp:1:1".to_string()),
+            format!("Got unexpected location {:?}", diagnostic.location)
+        );
+        anyhow::ensure!(
+            diagnostic.context == ["src/main.cpp: In function 'int main()':"],
+            format!("Got unexpected context {:?}", diagnostic.context)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn render() -> anyhow::Result<()> {
+        let rendered = diagnostics(GCC_OUTPUT)[0].to_string();
+        let expected = concat!(
+            "src/main.cpp: In function 'int main()':\n",
+            "error: 'undeclared' was not declared in this scope; did you mean 'declared'?\n",
+            "  --> src/main.cpp:4:12\n",
+            "    4 |     return undeclared;\n",
+            "      |            ^~~~~~~~~~\n",
+            "      |            declared\n",
+            "  = note: 'declared' declared here (src/main.cpp:3:9)\n",
+            "    3 |     int declared = 0;\n",
+            "      |         ^~~~~~~~\n",
+            "  = help: replace src/main.cpp:4:12-4:22 with `declared`\n",
+        );
+
+        anyhow::ensure!(
+            rendered == expected,
+            format!("Got unexpected rendering:\n{rendered}")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn render_template_instantiation() -> anyhow::Result<()> {
+        let rendered = diagnostics(TEMPLATE_OUTPUT)[0].to_string();
+        let expected = concat!(
+            "src/main.cpp: In instantiation of 'int f(T) [with T = int]':\n",
+            "src/main.cpp:7:13:   required from here\n",
+            "error: request for member 'size' in 't', which is of non-class type 'int'\n",
+            "  --> src/main.cpp:3:14\n",
+            "    3 |     return t.size();\n",
+            "      |            ~~^~~~\n",
+        );
+
+        anyhow::ensure!(
+            rendered == expected,
+            format!("Got unexpected rendering:\n{rendered}")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn sarif_results() -> anyhow::Result<()> {
+        let log = sarif(&diagnostics(GCC_OUTPUT));
+        let results = &log["runs"][0]["results"];
+
+        anyhow::ensure!(
+            results[0]["level"] == "error"
+                && results[0]["locations"][0]["physicalLocation"]["region"]["startLine"] == 4
+                && results[0]["fixes"][0]["artifactChanges"][0]["replacements"][0]
+                    ["insertedContent"]["text"]
+                    == "declared"
+                && results[1]["level"] == "warning",
+            format!("Got unexpected SARIF results:\n{results:#}")
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,421 @@
+use crate::{build, Context};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Record of the installed projects, inside the install root.
+const INSTALLED_FILE: &str = ".installed.json";
+
+/// Directory of a project's public headers, which are installed along with
+/// its binary or library.
+const INCLUDE_DIR: &str = "include";
+
+/// Files installed into a root, relative to it, by project name.
+type Installed = BTreeMap<String, Vec<PathBuf>>;
+
+/// Install the built `projects` into `root`: the binary of each into
+/// `root/bin`, or its static library into `root/lib`, and its public headers
+/// from `PROJECT_ROOT/include` into `root/include`, recording them so that
+/// they can be uninstalled.
+///
+/// Files which exist without having been installed, such as by another
+/// package manager, are only overwritten if `force`. Every project is checked
+/// before any file is copied, so that a failure doesn't leave `root` half
+/// installed.
+///
+/// Returns the name of each project and its installed files, relative to
+/// `root`.
+pub fn install(
+    projects: &[build::Artifacts],
+    root: &Path,
+    force: bool,
+) -> anyhow::Result<Vec<(String, Vec<PathBuf>)>> {
+    let mut installed = read_installed(root)?;
+    let projects = projects
+        .iter()
+        .map(|artifacts| Ok((artifacts.name.clone(), project_files(artifacts)?)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    for (index, (name, files)) in projects.iter().enumerate() {
+        let others = installed
+            .iter()
+            .filter(|(other_name, _)| *other_name != name)
+            .map(|(other_name, other_files)| (other_name, other_files.clone()))
+            .chain(projects[..index].iter().map(|(other_name, other_files)| {
+                let other_files = other_files.iter().map(|(_, file)| file.clone()).collect();
+                (other_name, other_files)
+            }));
+        for (other_name, other_files) in others {
+            if let Some((_, file)) = files.iter().find(|(_, file)| other_files.contains(file)) {
+                anyhow::bail!(format!(
+                    "{} is already installed by `{other_name}`!",
+                    root.join(file).display()
+                ));
+            }
+        }
+
+        let previous = installed.get(name).map(Vec::as_slice).unwrap_or_default();
+        if !force {
+            if let Some((_, file)) = files
+                .iter()
+                .find(|(_, file)| root.join(file).exists() && !previous.contains(file))
+            {
+                anyhow::bail!(format!(
+                    "{} already exists, but wasn't installed by `cppargo`! Use `--force` to overwrite it.",
+                    root.join(file).display()
+                ));
+            }
+        }
+    }
+
+    let mut installed_projects = Vec::with_capacity(projects.len());
+    for (name, files) in projects {
+        // Files no longer installed by a newer version are removed.
+        remove_files(root, &installed.remove(&name).unwrap_or_default())?;
+        for (src, file) in &files {
+            let dest = root.join(file);
+            if let Some(dir) = dest.parent() {
+                fs::create_dir_all(dir)
+                    .with_context(|| format!("Failed to create directory {}!", dir.display()))?;
+            }
+            // A running binary can't be overwritten, but can be replaced.
+            let _ = fs::remove_file(&dest);
+            fs::copy(src, &dest).with_context(|| {
+                format!("Failed to copy {} to {}!", src.display(), dest.display())
+            })?;
+        }
+
+        let files: Vec<_> = files.into_iter().map(|(_, file)| file).collect();
+        installed.insert(name.clone(), files.clone());
+        installed_projects.push((name, files));
+    }
+    write_installed(root, &installed)?;
+
+    Ok(installed_projects)
+}
+
+/// The files of a built project to install, along with where they're
+/// installed, relative to the install root.
+fn project_files(artifacts: &build::Artifacts) -> anyhow::Result<Vec<(PathBuf, PathBuf)>> {
+    let (artifact, dir) = match (&artifacts.binary, &artifacts.library) {
+        (Some(binary), _) => (binary, "bin"),
+        (None, Some(library)) => (library, "lib"),
+        (None, None) => anyhow::bail!(format!(
+            "Project {} has neither a `src/main.cpp` binary nor a library to install!",
+            artifacts.project_root.display()
+        )),
+    };
+    let Some(file_name) = artifact.file_name() else {
+        anyhow::bail!(format!("{} has no file name!", artifact.display()));
+    };
+
+    let mut files = vec![(artifact.clone(), Path::new(dir).join(file_name))];
+    let include_dir = artifacts.project_root.join(INCLUDE_DIR);
+    if include_dir.is_dir() {
+        for header in find_files(&include_dir)? {
+            let installed = Path::new(INCLUDE_DIR).join(header.strip_prefix(&include_dir)?);
+            files.push((header, installed));
+        }
+    }
+
+    Ok(files)
+}
+
+/// Remove the files of the project `name` installed into `root`.
+///
+/// Returns the removed files, relative to `root`.
+pub fn uninstall(name: &str, root: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut installed = read_installed(root)?;
+    let Some(files) = installed.remove(name) else {
+        anyhow::bail!(format!(
+            "Project `{name}` isn't installed in {}!",
+            root.display()
+        ));
+    };
+
+    remove_files(root, &files)?;
+    write_installed(root, &installed)?;
+
+    Ok(files)
+}
+
+/// Remove the installed `files`, along with the directories left empty.
+fn remove_files(root: &Path, files: &[PathBuf]) -> anyhow::Result<()> {
+    for file in files {
+        let path = root.join(file);
+        match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(err).with_context(|| format!("Failed to remove {}!", path.display()))
+            }
+        }
+
+        // Removing a directory fails once it isn't empty.
+        for dir in file.ancestors().skip(1) {
+            if dir.as_os_str().is_empty() || fs::remove_dir(root.join(dir)).is_err() {
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn find_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {}!", dir.display()))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(find_files(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+fn read_installed(root: &Path) -> anyhow::Result<Installed> {
+    let path = root.join(INSTALLED_FILE);
+    if !path.is_file() {
+        return Ok(Installed::new());
+    }
+
+    let invalid = || format!("Invalid record of installed projects {}!", path.display());
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&path)?).with_context(invalid)?;
+    let Some(projects) = json.as_object() else {
+        anyhow::bail!(invalid());
+    };
+
+    let mut installed = Installed::new();
+    for (name, files) in projects {
+        let files = files
+            .as_array()
+            .and_then(|files| {
+                files
+                    .iter()
+                    .map(|file| file.as_str().map(PathBuf::from))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .with_context(invalid)?;
+        installed.insert(name.clone(), files);
+    }
+
+    Ok(installed)
+}
+
+fn write_installed(root: &Path, installed: &Installed) -> anyhow::Result<()> {
+    let path = root.join(INSTALLED_FILE);
+    let json: serde_json::Map<_, _> = installed
+        .iter()
+        .map(|(name, files)| {
+            let files = files
+                .iter()
+                .map(|file| serde_json::Value::from(file.to_string_lossy()))
+                .collect();
+            (name.clone(), serde_json::Value::Array(files))
+        })
+        .collect();
+
+    fs::create_dir_all(root)
+        .with_context(|| format!("Failed to create directory {}!", root.display()))?;
+    fs::write(&path, serde_json::to_string_pretty(&json)? + "\n")
+        .with_context(|| format!("Failed to write {}!", path.display()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run::Runner;
+    use assert_fs::prelude::*;
+
+    fn artifacts(project_root: &Path, name: &str) -> build::Artifacts {
+        build::Artifacts {
+            project_root: project_root.to_path_buf(),
+            name: name.to_string(),
+            target_dir: project_root.join("target"),
+            objects_dir: project_root.join("target/release/obj"),
+            binary: Some(project_root.join("target/release").join(name)),
+            library: None,
+            benches: BTreeMap::new(),
+            examples: BTreeMap::new(),
+            runner: Runner::default(),
+        }
+    }
+
+    fn library(project_root: &Path, name: &str) -> build::Artifacts {
+        build::Artifacts {
+            binary: None,
+            library: Some(
+                project_root
+                    .join("target/release")
+                    .join(format!("lib{name}.a")),
+            ),
+            ..artifacts(project_root, name)
+        }
+    }
+
+    #[test]
+    fn install_and_uninstall() -> anyhow::Result<()> {
+        let project_root = assert_fs::TempDir::new()?;
+        project_root
+            .child("target/release/foo")
+            .write_str("binary")?;
+        project_root
+            .child("include/foo/foo.hpp")
+            .write_str("#pragma once\n")?;
+        let root = assert_fs::TempDir::new()?;
+
+        let [(name, files)] = &install(&[artifacts(&project_root, "foo")], &root, false)?[..]
+        else {
+            anyhow::bail!("Installed an unexpected number of projects!");
+        };
+        anyhow::ensure!(
+            name == "foo"
+                && *files
+                    == [
+                        PathBuf::from("bin/foo"),
+                        PathBuf::from("include/foo/foo.hpp")
+                    ],
+            format!("Installed unexpected files {files:?} of `{name}`!")
+        );
+        root.child("bin/foo").assert("binary");
+        root.child("include/foo/foo.hpp").assert("#pragma once\n");
+
+        // Another project can't overwrite the files of an installed one.
+        let other_root = assert_fs::TempDir::new()?;
+        other_root.child("target/release/bar").write_str("binary")?;
+        other_root.child("include/foo/foo.hpp").write_str("\n")?;
+        anyhow::ensure!(
+            install(&[artifacts(&other_root, "bar")], &root, true).is_err(),
+            "Overwrote the header of another project!"
+        );
+        // Installing again replaces the files of the previous install.
+        anyhow::ensure!(
+            install(&[artifacts(&project_root, "foo")], &root, false)?
+                == [(name.clone(), files.clone())],
+            "Failed to install project again!"
+        );
+
+        anyhow::ensure!(
+            uninstall("foo", &root)? == *files,
+            "Uninstalled unexpected files!"
+        );
+        root.child("bin").assert(predicates::path::missing());
+        root.child("include").assert(predicates::path::missing());
+        anyhow::ensure!(
+            uninstall("foo", &root).is_err(),
+            "Uninstalled a project which isn't installed!"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn only_overwrite_unrecorded_files_with_force() -> anyhow::Result<()> {
+        let project_root = assert_fs::TempDir::new()?;
+        project_root
+            .child("target/release/foo")
+            .write_str("binary")?;
+        project_root
+            .child("include/foo.hpp")
+            .write_str("#pragma once\n")?;
+        let root = assert_fs::TempDir::new()?;
+        root.child("include/foo.hpp").write_str("// other\n")?;
+
+        anyhow::ensure!(
+            install(&[artifacts(&project_root, "foo")], &root, false).is_err(),
+            "Overwrote a file which wasn't installed!"
+        );
+        root.child("include/foo.hpp").assert("// other\n");
+        root.child("bin/foo").assert(predicates::path::missing());
+
+        install(&[artifacts(&project_root, "foo")], &root, true)?;
+        root.child("include/foo.hpp").assert("#pragma once\n");
+
+        Ok(())
+    }
+
+    #[test]
+    fn install_libraries_along_with_binaries() -> anyhow::Result<()> {
+        let workspace_root = assert_fs::TempDir::new()?;
+        let (core, cli) = (
+            workspace_root.child("libs/core"),
+            workspace_root.child("apps/cli"),
+        );
+        core.child("target/release/libcore.a")
+            .write_str("archive")?;
+        core.child("include/core.hpp").write_str("#pragma once\n")?;
+        cli.child("target/release/cli").write_str("binary")?;
+        let root = assert_fs::TempDir::new()?;
+
+        let projects = [library(&core, "core"), artifacts(&cli, "cli")];
+        let installed = install(&projects, &root, false)?;
+        anyhow::ensure!(
+            installed
+                == [
+                    (
+                        "core".to_string(),
+                        vec![
+                            PathBuf::from("lib/libcore.a"),
+                            PathBuf::from("include/core.hpp")
+                        ]
+                    ),
+                    ("cli".to_string(), vec![PathBuf::from("bin/cli")]),
+                ],
+            format!("Installed unexpected files {installed:?}!")
+        );
+        root.child("lib/libcore.a").assert("archive");
+        root.child(INSTALLED_FILE)
+            .assert(predicates::str::contains("lib/libcore.a"));
+
+        uninstall("core", &root)?;
+        root.child("lib").assert(predicates::path::missing());
+        root.child("bin/cli").assert("binary");
+
+        Ok(())
+    }
+
+    #[test]
+    fn check_every_project_before_installing_any() -> anyhow::Result<()> {
+        let workspace_root = assert_fs::TempDir::new()?;
+        let (core, cli) = (
+            workspace_root.child("libs/core"),
+            workspace_root.child("apps/cli"),
+        );
+        core.child("target/release/libcore.a")
+            .write_str("archive")?;
+        cli.child("target/release/cli").write_str("binary")?;
+        let root = assert_fs::TempDir::new()?;
+        root.child("bin/cli").write_str("// other\n")?;
+
+        let projects = [library(&core, "core"), artifacts(&cli, "cli")];
+        anyhow::ensure!(
+            install(&projects, &root, false).is_err(),
+            "Overwrote a file which wasn't installed!"
+        );
+        root.child("lib").assert(predicates::path::missing());
+        root.child(INSTALLED_FILE)
+            .assert(predicates::path::missing());
+
+        // A project without a binary or a library has nothing to install.
+        let checked = build::Artifacts {
+            library: None,
+            ..library(&core, "core")
+        };
+        anyhow::ensure!(
+            install(&[checked, artifacts(&cli, "cli")], &root, true).is_err(),
+            "Installed a project without a binary or a library!"
+        );
+        root.child("bin/cli").assert("// other\n");
+
+        Ok(())
+    }
+}
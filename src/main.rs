@@ -1,39 +1,359 @@
 #![warn(clippy::pedantic)]
 
 use anyhow::{self, Context};
-use std::env;
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
 
 mod cli;
 use cli::{Cli, Commands, Parser};
 
+mod bench;
 mod build;
+mod build_script;
+mod cache;
+mod cfg;
+mod clean;
+mod coverage;
+mod diagnostic;
+mod install;
+mod manifest;
+mod message;
+mod modules;
+use message::{MessageFormat, Reporter};
 mod new;
+mod package;
 mod run;
+mod sanitizer;
+mod workspace;
 
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::New { path } => {
+        Commands::New { path, name } => {
             println!("Creating new project {}...", path.display());
-            new::main(&path)
+            new::main(&path, name.as_deref())
                 .with_context(|| format!("Failed to create project {}", &path.display()))?;
             println!("Project {} created successfully!", path.display());
         }
-        Commands::Build => {
-            println!("Building project...");
-            build::main(&env::current_dir()?).with_context(|| "Failed to build project.")?;
-            println!("Project built successfully!");
+        Commands::Build {
+            package,
+            release,
+            target,
+            examples,
+            compile_commands,
+            deny_warnings,
+            sanitize,
+            message_format,
+            sarif,
+        } => {
+            let reporter = Reporter::new(message_format);
+            let options = build::Options {
+                profile: profile(release),
+                target,
+                compile_commands,
+                deny_warnings,
+                sanitizers: sanitize.into_iter().collect(),
+                examples: if examples {
+                    build::Programs::All
+                } else {
+                    build::Programs::None
+                },
+                package,
+                ..Default::default()
+            };
+            let result = build_project(&env::current_dir()?, options, &reporter);
+            if let Some(sarif) = sarif {
+                reporter.write_sarif(&sarif)?;
+            }
+            result.with_context(|| "Failed to build project.")?;
         }
-        Commands::Run => {
-            println!("Building project...");
-            build::main(&env::current_dir()?)
-                .with_context(|| "Failed to build project before attempting to run it.")?;
-            println!("Project built successfully!");
-            println!("Running project...");
-            run::main(&env::current_dir()?).with_context(|| "Failed to run project")?;
+        Commands::Check {
+            package,
+            release,
+            target,
+            deny_warnings,
+            message_format,
+        } => check(package, release, target, deny_warnings, message_format)?,
+        Commands::Run {
+            package,
+            release,
+            target,
+            example,
+            runner,
+            deny_warnings,
+            sanitize,
+            message_format,
+        } => {
+            let reporter = Reporter::new(message_format);
+            let options = build::Options {
+                profile: profile(release),
+                target,
+                deny_warnings,
+                sanitizers: sanitize.into_iter().collect(),
+                examples: match &example {
+                    Some(name) => build::Programs::Named(name.clone()),
+                    None => build::Programs::None,
+                },
+                runner: runner.map(|runner| runner.split_whitespace().map(String::from).collect()),
+                package,
+                ..Default::default()
+            };
+            run(options, example.as_deref(), &reporter)?;
         }
+        Commands::Clean { package } => clean(package.as_deref())?,
+        Commands::Coverage {
+            package,
+            fail_under,
+        } => coverage(package, fail_under)?,
+        Commands::Bench { package } => bench(package)?,
+        Commands::Install {
+            package,
+            root,
+            force,
+        } => install(package, root, force)?,
+        Commands::Uninstall { name, root } => uninstall(&name, root)?,
+        Commands::Package { no_verify } => package(no_verify)?,
+    }
+
+    Ok(())
+}
+
+/// Build the project, and run its binary, or its `example`.
+fn run(options: build::Options, example: Option<&str>, reporter: &Reporter) -> anyhow::Result<()> {
+    let result = build_project(&env::current_dir()?, options, reporter);
+    let artifacts = single_project(
+        result.with_context(|| "Failed to build project before attempting to run it.")?,
+    )?;
+    reporter.status("Running project...");
+    let binary = match example {
+        Some(name) => &artifacts.examples[name],
+        None => artifacts.binary.as_ref().with_context(|| {
+            format!(
+                "Project {} has no `src/main.cpp` binary to run!",
+                artifacts.project_root.display()
+            )
+        })?,
+    };
+    run::main(binary, &artifacts.runner).with_context(|| "Failed to run project")
+}
+
+/// Compile the project without linking any binary, to check it for errors.
+fn check(
+    package: Option<String>,
+    release: bool,
+    target: Option<String>,
+    deny_warnings: bool,
+    message_format: MessageFormat,
+) -> anyhow::Result<()> {
+    let reporter = Reporter::new(message_format);
+    let options = build::Options {
+        profile: profile(release),
+        target,
+        deny_warnings,
+        check: true,
+        package,
+        ..Default::default()
+    };
+    build_project(&env::current_dir()?, options, &reporter)
+        .map(|_| ())
+        .with_context(|| "Failed to check project.")
+}
+
+/// Remove the `target/` directory of the project, or only the directories of
+/// the workspace member `package` inside it.
+fn clean(package: Option<&str>) -> anyhow::Result<()> {
+    let removed =
+        clean::main(&env::current_dir()?, package).with_context(|| "Failed to clean project.")?;
+    for dir in removed {
+        println!("Removed {}", dir.display());
+    }
+    println!("Project cleaned successfully!");
+
+    Ok(())
+}
+
+/// Build the project instrumented with `--coverage`, and measure the line
+/// coverage of running it.
+fn coverage(package: Option<String>, fail_under: Option<f64>) -> anyhow::Result<()> {
+    let reporter = Reporter::new(MessageFormat::Human);
+    let options = build::Options {
+        coverage: true,
+        package,
+        ..Default::default()
+    };
+    let result = build_project(&env::current_dir()?, options, &reporter);
+    let artifacts = single_project(
+        result.with_context(|| "Failed to build project before measuring its coverage.")?,
+    )?;
+    reporter.status("Measuring coverage...");
+    coverage::main(&env::current_dir()?, &artifacts, fail_under)
+        .with_context(|| "Failed to measure project coverage.")
+}
+
+/// Build the benchmarks of the project with the `release` profile, and time
+/// them.
+fn bench(package: Option<String>) -> anyhow::Result<()> {
+    let reporter = Reporter::new(MessageFormat::Human);
+    let options = build::Options {
+        profile: manifest::Profile::Release,
+        benches: build::Programs::All,
+        package,
+        ..Default::default()
+    };
+    let result = build_project(&env::current_dir()?, options, &reporter);
+    let projects = result.with_context(|| "Failed to build project before benchmarking it.")?;
+    reporter.status("Running benchmarks...");
+    for artifacts in &projects {
+        bench::main(&env::current_dir()?, artifacts)
+            .with_context(|| "Failed to benchmark project.")?;
+    }
+
+    Ok(())
+}
+
+/// Build the project with the `release` profile, and install it into `root`,
+/// overwriting files which weren't installed by `cppargo` only if `force`.
+fn install(package: Option<String>, root: Option<PathBuf>, force: bool) -> anyhow::Result<()> {
+    let root = install_root(root)?;
+    let reporter = Reporter::new(MessageFormat::Human);
+    let options = build::Options {
+        profile: manifest::Profile::Release,
+        archive: true,
+        package,
+        ..Default::default()
     };
+    let result = build_project(&env::current_dir()?, options, &reporter);
+    let projects = result.with_context(|| "Failed to build project before installing it.")?;
+    let installed = install::install(&projects, &root, force)
+        .with_context(|| format!("Failed to install project into {}.", root.display()))?;
+    for (name, files) in installed {
+        for file in files {
+            println!("Installed {}", root.join(file).display());
+        }
+        println!("Project `{name}` installed successfully!");
+    }
 
     Ok(())
 }
+
+/// Remove the files of the project `name` installed into `root`.
+fn uninstall(name: &str, root: Option<PathBuf>) -> anyhow::Result<()> {
+    let root = install_root(root)?;
+    let files = install::uninstall(name, &root)
+        .with_context(|| format!("Failed to uninstall `{name}`."))?;
+    for file in files {
+        println!("Removed {}", root.join(file).display());
+    }
+    println!("Project `{name}` uninstalled successfully!");
+
+    Ok(())
+}
+
+/// Archive the sources of the project, and unless `no_verify`, check that the
+/// archive builds.
+fn package(no_verify: bool) -> anyhow::Result<()> {
+    let package =
+        package::create(&env::current_dir()?).with_context(|| "Failed to package project.")?;
+    println!(
+        "Packaged {} files into {}",
+        package.files.len(),
+        package.archive.display()
+    );
+    if !no_verify {
+        let reporter = Reporter::new(MessageFormat::Human);
+        reporter.status(&format!("Verifying {}...", package.name));
+        package::verify(&package, |project_root| {
+            build_project(project_root, build::Options::default(), &reporter).map(|_| ())
+        })
+        .with_context(|| format!("Failed to verify {}.", package.archive.display()))?;
+    }
+    println!("Project `{}` packaged successfully!", package.name);
+
+    Ok(())
+}
+
+/// The `root` to install projects into, or otherwise the `cppargo_home`.
+fn install_root(root: Option<PathBuf>) -> anyhow::Result<PathBuf> {
+    match root.or_else(cppargo_home) {
+        Some(root) => Ok(root),
+        None => anyhow::bail!("Failed to find the home directory! Set `CPPARGO_HOME` or `--root`."),
+    }
+}
+
+fn profile(release: bool) -> manifest::Profile {
+    if release {
+        manifest::Profile::Release
+    } else {
+        manifest::Profile::Dev
+    }
+}
+
+/// Build the project containing `current_dir`, or the selected workspace
+/// members, reporting the start and end of the build.
+///
+/// Compilers are run through the `CPPARGO_COMPILER_LAUNCHER`, if set, and
+/// objects are otherwise cached inside the `cppargo_home`, unless
+/// `CPPARGO_CACHE=off`. The cache is then trimmed to `CPPARGO_CACHE_SIZE`
+/// MiB, or 1 GiB.
+fn build_project(
+    current_dir: &Path,
+    mut options: build::Options,
+    reporter: &Reporter,
+) -> anyhow::Result<Vec<build::Artifacts>> {
+    options.compiler_launcher = env::var("CPPARGO_COMPILER_LAUNCHER")
+        .ok()
+        .map(|launcher| launcher.split_whitespace().map(String::from).collect())
+        .filter(|launcher: &Vec<String>| !launcher.is_empty());
+    options.cache_dir = match env::var("CPPARGO_CACHE") {
+        Ok(cache) if cache == "off" => None,
+        _ => cppargo_home().map(|home| home.join("cache")),
+    };
+    let cache_size = match env::var("CPPARGO_CACHE_SIZE") {
+        Ok(size) => size
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .and_then(|mib| mib.checked_mul(1024 * 1024))
+            .with_context(|| {
+                format!(
+                    "Invalid `CPPARGO_CACHE_SIZE` `{size}`! Expected a size in MiB, such as `1024`."
+                )
+            })?,
+        Err(_) => cache::DEFAULT_MAX_SIZE,
+    };
+
+    reporter.build_started(options.check);
+    let result = build::main(current_dir, &options, reporter);
+    reporter.build_finished(result.is_ok(), options.check);
+
+    if let Some(cache_dir) = &options.cache_dir {
+        if let Err(err) = cache::trim(cache_dir, cache_size) {
+            eprintln!("[WARN] Failed to trim the object cache: {err:#}");
+        }
+    }
+
+    result
+}
+
+/// Directory of the files cppargo keeps across projects, `CPPARGO_HOME` or
+/// otherwise `~/.cppargo`.
+fn cppargo_home() -> Option<PathBuf> {
+    env::var_os("CPPARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cppargo")))
+}
+
+/// The artifacts of the only project built, since commands such as `run`
+/// can't act on every member of a workspace at once.
+fn single_project(projects: Vec<build::Artifacts>) -> anyhow::Result<build::Artifacts> {
+    let mut projects = projects.into_iter();
+    match (projects.next(), projects.next()) {
+        (Some(artifacts), None) => Ok(artifacts),
+        _ => anyhow::bail!(
+            "Several workspace members were built! Select one of them with `-p NAME`."
+        ),
+    }
+}
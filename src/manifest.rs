@@ -0,0 +1,1399 @@
+use crate::{cfg, Context};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Project configuration read from a `Cppargo.toml` manifest.
+pub struct Manifest {
+    pub name: String,
+    pub metadata: Metadata,
+    pub build: BuildConfig,
+    pub lints: LintsConfig,
+    pub profiles: Profiles,
+    pub run: RunConfig,
+    pub package: PackageConfig,
+    /// Platform-conditional `[target.'cfg(...)']` tables.
+    pub targets: Vec<TargetConfig>,
+    /// Cross-compilation `[target.<triple>]` tables, by target triple.
+    pub triples: BTreeMap<String, TripleConfig>,
+}
+
+/// Metadata of the project from the manifest `[project]` table, besides its
+/// name.
+#[derive(Debug, Default, PartialEq)]
+pub struct Metadata {
+    /// Version of the project, such as `0.1.0`, required to package it.
+    pub version: Option<String>,
+    pub authors: Vec<String>,
+    pub description: Option<String>,
+    /// License of the project, such as `MIT OR Apache-2.0`.
+    pub license: Option<String>,
+}
+
+/// Configuration from the manifest `[build]` table.
+#[derive(Clone, Default)]
+pub struct BuildConfig {
+    /// C standard given to the C compiler as `-std=<c-standard>`.
+    pub c_standard: Option<String>,
+    /// Globs, relative to the project root, of the source files to compile
+    /// instead of every source file inside `src/`.
+    pub sources: Option<Vec<String>>,
+    /// Globs, relative to the project root, of source files not to compile.
+    pub exclude: Vec<String>,
+    /// Fail instead of warning when an entry can't be read while searching
+    /// for source files.
+    pub strict_sources: bool,
+    /// Write a `compile_commands.json` at the project root on every build.
+    pub compile_commands: bool,
+    /// Build script run before the project is built, instead of
+    /// `PROJECT_ROOT/build.cpp`.
+    pub script: Option<BuildScript>,
+    /// Header, relative to the project root, precompiled once and included
+    /// in every C++ source file.
+    pub precompiled_header: Option<PathBuf>,
+    /// Command every compiler invocation is prefixed with, such as
+    /// `["ccache"]`.
+    pub compiler_launcher: Option<Vec<String>>,
+}
+
+/// A build script, which generates code or configures the build of a project
+/// through the `cppargo:` lines it prints.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BuildScript {
+    /// A C++ or C source file, relative to the project root, which is
+    /// compiled for the host and run.
+    Source(PathBuf),
+    /// A command, such as `["python3", "gen.py"]`, which is run as is.
+    Command(Vec<String>),
+}
+
+/// Configuration from the manifest `[package]` table.
+#[derive(Debug, Default, PartialEq)]
+pub struct PackageConfig {
+    /// Globs, relative to the project root, of extra files to package along
+    /// with the manifest, sources and headers, such as `README.md`.
+    pub include: Vec<String>,
+}
+
+/// Configuration from the manifest `[run]` table.
+#[derive(Debug, Default, PartialEq)]
+pub struct RunConfig {
+    /// Command, such as `["valgrind", "--leak-check=full"]`, that the project
+    /// binary is run through, given as its last argument.
+    pub runner: Option<Vec<String>>,
+    /// Environment variables set for the project binary, from the `[run.env]`
+    /// table.
+    pub env: BTreeMap<String, String>,
+}
+
+/// Configuration from the manifest `[lints]` table.
+#[derive(Default)]
+pub struct LintsConfig {
+    pub level: WarningLevel,
+    /// Extra `-W` flags, such as `-Wshadow` or `-Wno-unused-parameter`.
+    pub flags: Vec<String>,
+    /// Turn every warning into an error.
+    pub deny_warnings: bool,
+}
+
+/// Which set of compiler warnings is enabled.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum WarningLevel {
+    /// Disable all warnings.
+    None,
+    /// The compiler's own default warnings.
+    #[default]
+    Default,
+    /// `-Wall`.
+    All,
+    /// `-Wall -Wextra`.
+    Extra,
+    /// `-Wall -Wextra -Wpedantic`.
+    Pedantic,
+}
+
+impl FromStr for WarningLevel {
+    type Err = anyhow::Error;
+
+    fn from_str(level: &str) -> anyhow::Result<Self> {
+        match level {
+            "none" => Ok(Self::None),
+            "default" => Ok(Self::Default),
+            "all" => Ok(Self::All),
+            "extra" => Ok(Self::Extra),
+            "pedantic" => Ok(Self::Pedantic),
+            _ => anyhow::bail!(format!(
+                "Invalid warning level `{level}`! Expected one of `none`, `default`, `all`, `extra` or `pedantic`."
+            )),
+        }
+    }
+}
+
+/// A build profile, trading off between compilation speed and binary speed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Profile {
+    /// Unoptimized builds for development.
+    #[default]
+    Dev,
+    /// Optimized builds for benchmarking and shipping.
+    Release,
+}
+
+/// Configuration from a manifest `[profile.<name>]` table, on top of the
+/// defaults of the profile.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProfileConfig {
+    /// Optimization level given to the compiler as `-O<opt-level>`.
+    pub opt_level: Option<String>,
+    /// Include debug information with `-g`.
+    pub debug: bool,
+    /// Keep `assert`s enabled, instead of defining `NDEBUG`.
+    pub debug_assertions: bool,
+    /// Link-time optimization, enabled with `-flto`.
+    pub lto: Lto,
+    /// Linker used instead of the compiler's default, with `-fuse-ld=`.
+    pub linker: Option<Linker>,
+    /// What is stripped from the built binaries.
+    pub strip: Strip,
+    /// Move the debug information of the built binaries into `BINARY.debug`
+    /// files, which they link to with a `.gnu_debuglink` section.
+    pub split_debuginfo: bool,
+}
+
+/// How much of the program is optimized at link time.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Lto {
+    #[default]
+    Off,
+    /// Optimize the program in parallel partitions, trading some
+    /// optimization for faster links.
+    Thin,
+    /// Optimize the whole program at once.
+    Full,
+}
+
+impl FromStr for Lto {
+    type Err = anyhow::Error;
+
+    fn from_str(lto: &str) -> anyhow::Result<Self> {
+        match lto {
+            "off" => Ok(Self::Off),
+            "thin" => Ok(Self::Thin),
+            "full" => Ok(Self::Full),
+            _ => anyhow::bail!(format!(
+                "Invalid `lto` `{lto}`! Expected one of `off`, `thin` or `full`."
+            )),
+        }
+    }
+}
+
+/// What is stripped from the built binaries.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Strip {
+    #[default]
+    None,
+    /// Debug information.
+    Debuginfo,
+    /// Debug information and the symbol table.
+    Symbols,
+}
+
+impl FromStr for Strip {
+    type Err = anyhow::Error;
+
+    fn from_str(strip: &str) -> anyhow::Result<Self> {
+        match strip {
+            "none" => Ok(Self::None),
+            "debuginfo" => Ok(Self::Debuginfo),
+            "symbols" => Ok(Self::Symbols),
+            _ => anyhow::bail!(format!(
+                "Invalid `strip` `{strip}`! Expected one of `none`, `debuginfo` or `symbols`."
+            )),
+        }
+    }
+}
+
+/// A linker the compiler can be told to use instead of its default one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Linker {
+    Mold,
+    Lld,
+    Gold,
+    Bfd,
+}
+
+impl Linker {
+    /// Name of the linker, as given to `-fuse-ld=`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Mold => "mold",
+            Self::Lld => "lld",
+            Self::Gold => "gold",
+            Self::Bfd => "bfd",
+        }
+    }
+}
+
+impl FromStr for Linker {
+    type Err = anyhow::Error;
+
+    fn from_str(linker: &str) -> anyhow::Result<Self> {
+        match linker {
+            "mold" => Ok(Self::Mold),
+            "lld" => Ok(Self::Lld),
+            "gold" => Ok(Self::Gold),
+            "bfd" => Ok(Self::Bfd),
+            _ => anyhow::bail!(format!(
+                "Invalid `linker` `{linker}`! Expected one of `mold`, `lld`, `gold` or `bfd`."
+            )),
+        }
+    }
+}
+
+/// The configuration of every profile.
+pub struct Profiles {
+    pub dev: ProfileConfig,
+    pub release: ProfileConfig,
+}
+
+/// Valid values of `opt-level`.
+const OPT_LEVELS: [&str; 8] = ["0", "1", "2", "3", "s", "z", "g", "fast"];
+
+/// A `[target.'cfg(...)']` table, which only applies on matching platforms.
+pub struct TargetConfig {
+    pub cfg: cfg::Cfg,
+    pub build: BuildConfig,
+}
+
+/// A `[target.<triple>]` table, configuring how to cross-compile for a target
+/// triple such as `aarch64-linux-gnu`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TripleConfig {
+    /// C++ compiler, also used for linking, instead of `<triple>-g++`.
+    pub compiler: Option<String>,
+    /// C compiler, instead of `<triple>-gcc`.
+    pub c_compiler: Option<String>,
+    /// Root directory of the target headers and libraries, given to the
+    /// compiler as `--sysroot=<sysroot>`.
+    pub sysroot: Option<String>,
+    /// Extra flags for compiling every source file.
+    pub flags: Vec<String>,
+    /// Extra flags for linking.
+    pub link_flags: Vec<String>,
+    /// Command, such as `["qemu-aarch64"]`, that runs the target binaries,
+    /// which are given as its last argument.
+    pub runner: Option<Vec<String>>,
+}
+
+impl Manifest {
+    /// The `[build]` configuration with every `[target.'cfg(...)'.build]`
+    /// table matching `platform` merged into it.
+    pub fn build_config(&self, platform: &cfg::Platform) -> BuildConfig {
+        let mut build = self.build.clone();
+        for target in self.targets.iter().filter(|t| t.cfg.matches(platform)) {
+            build.merge(&target.build);
+        }
+
+        build
+    }
+}
+
+impl ProfileConfig {
+    fn default_for(profile: Profile) -> Self {
+        match profile {
+            Profile::Dev => Self {
+                opt_level: None,
+                debug: false,
+                debug_assertions: true,
+                lto: Lto::Off,
+                linker: None,
+                strip: Strip::None,
+                split_debuginfo: false,
+            },
+            Profile::Release => Self {
+                opt_level: Some("3".to_string()),
+                debug: false,
+                debug_assertions: false,
+                lto: Lto::Off,
+                linker: None,
+                strip: Strip::None,
+                split_debuginfo: false,
+            },
+        }
+    }
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        Self {
+            dev: ProfileConfig::default_for(Profile::Dev),
+            release: ProfileConfig::default_for(Profile::Release),
+        }
+    }
+}
+
+impl Profiles {
+    pub fn get(&self, profile: Profile) -> &ProfileConfig {
+        match profile {
+            Profile::Dev => &self.dev,
+            Profile::Release => &self.release,
+        }
+    }
+}
+
+impl BuildConfig {
+    /// Merge a more specific configuration into this one. Single values are
+    /// overridden, while lists of globs are extended.
+    fn merge(&mut self, other: &Self) {
+        if other.c_standard.is_some() {
+            self.c_standard.clone_from(&other.c_standard);
+        }
+        if let Some(other_sources) = &other.sources {
+            self.sources
+                .get_or_insert_with(Vec::new)
+                .extend(other_sources.iter().cloned());
+        }
+        self.exclude.extend(other.exclude.iter().cloned());
+        self.strict_sources |= other.strict_sources;
+        self.compile_commands |= other.compile_commands;
+        if other.script.is_some() {
+            self.script.clone_from(&other.script);
+        }
+        if other.precompiled_header.is_some() {
+            self.precompiled_header
+                .clone_from(&other.precompiled_header);
+        }
+        if other.compiler_launcher.is_some() {
+            self.compiler_launcher.clone_from(&other.compiler_launcher);
+        }
+    }
+}
+
+/// Configuration from the `[workspace]` table of a workspace root manifest.
+#[derive(Debug, PartialEq)]
+pub struct WorkspaceConfig {
+    /// Globs of the member project directories, relative to the workspace
+    /// root.
+    pub members: Vec<String>,
+    /// Whether the workspace root is a project too, with a `[project]` table.
+    pub root_project: bool,
+}
+
+/// Read the `[workspace]` table of `manifest_path`, if it has one.
+pub fn read_workspace(manifest_path: &Path) -> anyhow::Result<Option<WorkspaceConfig>> {
+    let manifest = toml_edit::DocumentMut::from_str(&fs::read_to_string(manifest_path)?)
+        .with_context(|| format!("Failed to parse manifest {}!", manifest_path.display()))?;
+
+    let Some(workspace) = manifest.get("workspace") else {
+        return Ok(None);
+    };
+    let members = get_string_array(workspace, "members")
+        .with_context(|| {
+            format!(
+                "Invalid `[workspace]` table in manifest {}!",
+                manifest_path.display()
+            )
+        })?
+        .unwrap_or_default();
+
+    Ok(Some(WorkspaceConfig {
+        members,
+        root_project: manifest.contains_key("project"),
+    }))
+}
+
+pub fn read(project_manifest: &Path) -> anyhow::Result<Manifest> {
+    let manifest = toml_edit::DocumentMut::from_str(&fs::read_to_string(project_manifest)?)
+        .with_context(|| {
+            format!(
+                "Failed to parse project manifest {}!",
+                project_manifest.display()
+            )
+        })?;
+
+    let Some(name) = manifest["project"]["name"].as_str() else {
+        anyhow::bail!("Failed to gather project name!")
+    };
+    let metadata = parse_metadata(&manifest["project"]).with_context(|| {
+        format!(
+            "Invalid `[project]` table in project manifest {}!",
+            project_manifest.display()
+        )
+    })?;
+
+    let build = match manifest.get("build") {
+        Some(build) => parse_build_config(build).with_context(|| {
+            format!(
+                "Invalid `[build]` table in project manifest {}!",
+                project_manifest.display()
+            )
+        })?,
+        None => BuildConfig::default(),
+    };
+
+    let lints = match manifest.get("lints") {
+        Some(lints) => parse_lints_config(lints).with_context(|| {
+            format!(
+                "Invalid `[lints]` table in project manifest {}!",
+                project_manifest.display()
+            )
+        })?,
+        None => LintsConfig::default(),
+    };
+
+    let run = match manifest.get("run") {
+        Some(run) => parse_run_config(run).with_context(|| {
+            format!(
+                "Invalid `[run]` table in project manifest {}!",
+                project_manifest.display()
+            )
+        })?,
+        None => RunConfig::default(),
+    };
+
+    let package = match manifest.get("package") {
+        Some(package) => PackageConfig {
+            include: get_string_array(package, "include")
+                .with_context(|| {
+                    format!(
+                        "Invalid `[package]` table in project manifest {}!",
+                        project_manifest.display()
+                    )
+                })?
+                .unwrap_or_default(),
+        },
+        None => PackageConfig::default(),
+    };
+
+    let profiles = match manifest.get("profile") {
+        Some(profiles) => parse_profiles(profiles).with_context(|| {
+            format!(
+                "Invalid `[profile]` table in project manifest {}!",
+                project_manifest.display()
+            )
+        })?,
+        None => Profiles::default(),
+    };
+
+    let (targets, triples) = match manifest.get("target") {
+        Some(targets) => parse_targets(targets, &build).with_context(|| {
+            format!(
+                "Invalid `[target]` table in project manifest {}!",
+                project_manifest.display()
+            )
+        })?,
+        None => (Vec::new(), BTreeMap::new()),
+    };
+
+    Ok(Manifest {
+        name: name.to_string(),
+        metadata,
+        build,
+        lints,
+        profiles,
+        run,
+        package,
+        targets,
+        triples,
+    })
+}
+
+fn parse_metadata(project: &toml_edit::Item) -> anyhow::Result<Metadata> {
+    Ok(Metadata {
+        version: get_version(project)?,
+        authors: get_string_array(project, "authors")?.unwrap_or_default(),
+        description: get_string(project, "description")?,
+        license: get_string(project, "license")?,
+    })
+}
+
+/// The `version` of the `[project]` table, made of dot separated numbers
+/// optionally followed by a `-` pre-release or `+` build suffix, as in
+/// `1.2.3-rc.1`.
+fn get_version(project: &toml_edit::Item) -> anyhow::Result<Option<String>> {
+    let Some(version) = get_string(project, "version")? else {
+        return Ok(None);
+    };
+
+    let numbers = version.split(['-', '+']).next().unwrap_or_default();
+    let is_valid = numbers
+        .split('.')
+        .all(|number| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'));
+    anyhow::ensure!(
+        is_valid,
+        format!("Invalid `version` `{version}`! Expected one such as `0.1.0`.")
+    );
+
+    Ok(Some(version))
+}
+
+fn parse_build_config(build: &toml_edit::Item) -> anyhow::Result<BuildConfig> {
+    Ok(BuildConfig {
+        c_standard: get_string(build, "c-standard")?,
+        sources: get_string_array(build, "sources")?,
+        exclude: get_string_array(build, "exclude")?.unwrap_or_default(),
+        strict_sources: get_bool(build, "strict-sources")?.unwrap_or_default(),
+        compile_commands: get_bool(build, "compile-commands")?.unwrap_or_default(),
+        script: match build.get("script") {
+            Some(script) if script.is_str() => get_string(build, "script")?
+                .map(|source| BuildScript::Source(PathBuf::from(source))),
+            Some(_) => match get_string_array(build, "script")? {
+                Some(command) if command.is_empty() => {
+                    anyhow::bail!("`script` must not be empty!")
+                }
+                command => command.map(BuildScript::Command),
+            },
+            None => None,
+        },
+        precompiled_header: get_string(build, "precompiled-header")?.map(PathBuf::from),
+        compiler_launcher: match build.get("compiler-launcher") {
+            Some(launcher) if launcher.is_str() => get_string(build, "compiler-launcher")?
+                .map(|launcher| launcher.split_whitespace().map(String::from).collect()),
+            Some(_) => get_string_array(build, "compiler-launcher")?,
+            None => None,
+        }
+        .map(|launcher: Vec<String>| {
+            anyhow::ensure!(
+                !launcher.is_empty(),
+                "`compiler-launcher` must not be empty!"
+            );
+            Ok(launcher)
+        })
+        .transpose()?,
+    })
+}
+
+fn parse_lints_config(lints: &toml_edit::Item) -> anyhow::Result<LintsConfig> {
+    let flags = get_string_array(lints, "flags")?.unwrap_or_default();
+    if let Some(flag) = flags.iter().find(|flag| !flag.starts_with("-W")) {
+        anyhow::bail!(format!("Invalid lint flag `{flag}`! Expected a `-W` flag."));
+    }
+
+    Ok(LintsConfig {
+        level: match get_string(lints, "level")? {
+            Some(level) => level.parse()?,
+            None => WarningLevel::default(),
+        },
+        flags,
+        deny_warnings: get_bool(lints, "deny-warnings")?.unwrap_or_default(),
+    })
+}
+
+fn parse_run_config(run: &toml_edit::Item) -> anyhow::Result<RunConfig> {
+    let mut env = BTreeMap::new();
+    if let Some(env_table) = run.get("env") {
+        let Some(env_table) = env_table.as_table_like() else {
+            anyhow::bail!("`env` must be a table!");
+        };
+        for (key, value) in env_table.iter() {
+            let Some(value) = value.as_str() else {
+                anyhow::bail!(format!("Environment variable `{key}` must be a string!"));
+            };
+            env.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    Ok(RunConfig {
+        runner: get_runner(run)?,
+        env,
+    })
+}
+
+fn parse_profiles(profiles: &toml_edit::Item) -> anyhow::Result<Profiles> {
+    let Some(profiles) = profiles.as_table_like() else {
+        anyhow::bail!("`profile` must be a table!");
+    };
+
+    let mut profile_configs = Profiles::default();
+    for (key, profile) in profiles.iter() {
+        let profile_config = match key {
+            "dev" => &mut profile_configs.dev,
+            "release" => &mut profile_configs.release,
+            _ => anyhow::bail!(format!(
+                "Unknown profile `{key}`! Expected `dev` or `release`."
+            )),
+        };
+        parse_profile_config(profile, profile_config)
+            .with_context(|| format!("Invalid `[profile.{key}]` table!"))?;
+    }
+
+    Ok(profile_configs)
+}
+
+fn parse_profile_config(
+    profile: &toml_edit::Item,
+    profile_config: &mut ProfileConfig,
+) -> anyhow::Result<()> {
+    // Like in `cargo`, optimization levels may be given as integers.
+    if let Some(opt_level) = profile.get("opt-level") {
+        let opt_level = match (opt_level.as_integer(), opt_level.as_str()) {
+            (Some(integer), _) => integer.to_string(),
+            (_, Some(str)) => str.to_string(),
+            _ => anyhow::bail!("`opt-level` must be an integer or a string!"),
+        };
+        anyhow::ensure!(
+            OPT_LEVELS.contains(&opt_level.as_str()),
+            format!(
+                "Invalid `opt-level` `{opt_level}`! Expected one of {}.",
+                OPT_LEVELS.map(|level| format!("`{level}`")).join(", ")
+            )
+        );
+        profile_config.opt_level = Some(opt_level);
+    }
+    if let Some(debug) = get_bool(profile, "debug")? {
+        profile_config.debug = debug;
+    }
+    if let Some(debug_assertions) = get_bool(profile, "debug-assertions")? {
+        profile_config.debug_assertions = debug_assertions;
+    }
+    if let Some(lto) = get_string(profile, "lto")? {
+        profile_config.lto = lto.parse()?;
+    }
+    if let Some(linker) = get_string(profile, "linker")? {
+        profile_config.linker = Some(linker.parse()?);
+    }
+    if let Some(strip) = get_string(profile, "strip")? {
+        profile_config.strip = strip.parse()?;
+    }
+    if let Some(split_debuginfo) = get_bool(profile, "split-debuginfo")? {
+        profile_config.split_debuginfo = split_debuginfo;
+    }
+    anyhow::ensure!(
+        !profile_config.split_debuginfo || profile_config.debug,
+        "`split-debuginfo` requires `debug = true`, since there's no debug information to split otherwise!"
+    );
+
+    Ok(())
+}
+
+/// Parse both the `[target.'cfg(...)']` and the `[target.<triple>]` tables.
+///
+/// The `sources` of a `[target.'cfg(...)'.build]` table are added to the ones
+/// of the `[build]` table, so they are only allowed if it has `sources` too,
+/// rather than silently replacing every source file inside `src/` on some
+/// platforms only.
+fn parse_targets(
+    targets: &toml_edit::Item,
+    base_build: &BuildConfig,
+) -> anyhow::Result<(Vec<TargetConfig>, BTreeMap<String, TripleConfig>)> {
+    let Some(targets) = targets.as_table_like() else {
+        anyhow::bail!("`target` must be a table!");
+    };
+
+    let mut target_configs = Vec::new();
+    let mut triple_configs = BTreeMap::new();
+    for (key, target) in targets.iter() {
+        if !key.starts_with("cfg(") {
+            let triple_config = parse_triple_config(target)
+                .with_context(|| format!("Invalid `[target.{key}]` table!"))?;
+            triple_configs.insert(key.to_string(), triple_config);
+            continue;
+        }
+
+        let cfg = cfg::Cfg::parse(key)
+            .with_context(|| format!("Invalid target `{key}`! Expected `cfg(...)`."))?;
+        let build = match target.get("build") {
+            Some(build) => parse_build_config(build)
+                .with_context(|| format!("Invalid `[target.'{key}'.build]` table!"))?,
+            None => BuildConfig::default(),
+        };
+        anyhow::ensure!(
+            build.sources.is_none() || base_build.sources.is_some(),
+            format!(
+                "`[target.'{key}'.build] sources` are added to `[build] sources`, which is \
+                missing! Set it to `[]` to only compile the platform sources, or to \
+                `[\"src/**\"]` to also compile every source file inside `src`."
+            )
+        );
+        target_configs.push(TargetConfig { cfg, build });
+    }
+
+    Ok((target_configs, triple_configs))
+}
+
+fn parse_triple_config(triple: &toml_edit::Item) -> anyhow::Result<TripleConfig> {
+    Ok(TripleConfig {
+        compiler: get_string(triple, "compiler")?,
+        c_compiler: get_string(triple, "c-compiler")?,
+        sysroot: get_string(triple, "sysroot")?,
+        flags: get_string_array(triple, "flags")?.unwrap_or_default(),
+        link_flags: get_string_array(triple, "link-flags")?.unwrap_or_default(),
+        runner: get_runner(triple)?,
+    })
+}
+
+fn get_runner(table: &toml_edit::Item) -> anyhow::Result<Option<Vec<String>>> {
+    match get_string_array(table, "runner")? {
+        Some(runner) if runner.is_empty() => anyhow::bail!("`runner` must not be empty!"),
+        runner => Ok(runner),
+    }
+}
+
+fn get_string(table: &toml_edit::Item, key: &str) -> anyhow::Result<Option<String>> {
+    match table.get(key) {
+        Some(value) => match value.as_str() {
+            Some(str) => Ok(Some(str.to_string())),
+            None => anyhow::bail!(format!("`{key}` must be a string!")),
+        },
+        None => Ok(None),
+    }
+}
+
+fn get_bool(table: &toml_edit::Item, key: &str) -> anyhow::Result<Option<bool>> {
+    match table.get(key) {
+        Some(value) => match value.as_bool() {
+            Some(bool) => Ok(Some(bool)),
+            None => anyhow::bail!(format!("`{key}` must be a boolean!")),
+        },
+        None => Ok(None),
+    }
+}
+
+fn get_string_array(table: &toml_edit::Item, key: &str) -> anyhow::Result<Option<Vec<String>>> {
+    let Some(value) = table.get(key) else {
+        return Ok(None);
+    };
+
+    let strings = value.as_array().and_then(|array| {
+        array
+            .iter()
+            .map(|value| value.as_str().map(str::to_string))
+            .collect::<Option<Vec<String>>>()
+    });
+
+    match strings {
+        Some(strings) => Ok(Some(strings)),
+        None => anyhow::bail!(format!("`{key}` must be an array of strings!")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+
+    const PROJECT_MANIFEST: &str = "[project]\nname = \"foo\"\n";
+    const PROJEJCT_MANIFEST_WITH_NO_NAME: &str = "[project]\nname =\n";
+
+    #[cfg(test)]
+    mod read {
+        use super::*;
+
+        #[test]
+        fn succeed() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_manifest = tmp_dir.child("Cppargo.toml");
+            project_manifest.write_str(PROJECT_MANIFEST)?;
+
+            let manifest = read(project_manifest.path())?;
+
+            anyhow::ensure!(
+                manifest.name == "foo",
+                format!(
+                    "Got wrong project name!\nExpected: foo\nGot: {}\n",
+                    manifest.name
+                )
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn metadata_and_package() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_manifest = tmp_dir.child("Cppargo.toml");
+            project_manifest.write_str(concat!(
+                "[project]\n",
+                "name = \"foo\"\n",
+                "version = \"1.2.3-rc.1\"\n",
+                "authors = [\"Jane Doe <jane@example.com>\"]\n",
+                "description = \"A foo\"\n",
+                "license = \"MIT\"\n",
+                "[package]\n",
+                "include = [\"README.md\", \"data/**\"]\n"
+            ))?;
+
+            let manifest = read(project_manifest.path())?;
+            let expected = Metadata {
+                version: Some("1.2.3-rc.1".to_string()),
+                authors: vec!["Jane Doe <jane@example.com>".to_string()],
+                description: Some("A foo".to_string()),
+                license: Some("MIT".to_string()),
+            };
+            anyhow::ensure!(
+                manifest.metadata == expected
+                    && manifest.package.include == ["README.md", "data/**"],
+                format!(
+                    "Got unexpected metadata {:?} and package {:?}!",
+                    manifest.metadata, manifest.package
+                )
+            );
+            project_manifest.write_str(PROJECT_MANIFEST)?;
+            let manifest = read(project_manifest.path())?;
+            anyhow::ensure!(
+                manifest.metadata == Metadata::default(),
+                format!("Got unexpected metadata {:?}!", manifest.metadata)
+            );
+
+            project_manifest.write_str(&format!("{PROJECT_MANIFEST}authors = \"Jane\"\n"))?;
+            anyhow::ensure!(
+                read(project_manifest.path()).is_err(),
+                "Accepted authors which aren't an array!"
+            );
+
+            for version in ["1", "\"\"", "\"1..0\"", "\"v1.0\"", "\"1.0 beta\""] {
+                project_manifest.write_str(&format!("{PROJECT_MANIFEST}version = {version}\n"))?;
+                anyhow::ensure!(
+                    read(project_manifest.path()).is_err(),
+                    format!("Accepted invalid version {version}!")
+                );
+            }
+
+            Ok(())
+        }
+
+        #[test]
+        fn no_name_in_manifest() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_manifest = tmp_dir.child("Cppargo.toml");
+            project_manifest.write_str(PROJEJCT_MANIFEST_WITH_NO_NAME)?;
+
+            match read(project_manifest.path()) {
+                Err(err) => {
+                    if err.to_string()
+                        == format!(
+                            "Failed to parse project manifest {}!",
+                            project_manifest.path().display()
+                        )
+                    {
+                        return Ok(());
+                    }
+
+                    anyhow::bail!(err);
+                }
+                Ok(manifest) => {
+                    anyhow::bail!(format!("Found unexpected name {}!", manifest.name));
+                }
+            }
+        }
+
+        #[test]
+        fn build_c_standard() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_manifest = tmp_dir.child("Cppargo.toml");
+            project_manifest.write_str(concat!(
+                "[project]\nname = \"foo\"\n",
+                "[build]\nc-standard = \"c11\"\n"
+            ))?;
+
+            let manifest = read(project_manifest.path())?;
+
+            anyhow::ensure!(
+                manifest.build.c_standard.as_deref() == Some("c11"),
+                "Failed to read C standard from manifest!"
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn fail_non_string_c_standard() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_manifest = tmp_dir.child("Cppargo.toml");
+            project_manifest.write_str(concat!(
+                "[project]\nname = \"foo\"\n",
+                "[build]\nc-standard = 11\n"
+            ))?;
+
+            anyhow::ensure!(
+                read(project_manifest.path()).is_err(),
+                "Accepted a non-string C standard!"
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn build_sources_and_exclude() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_manifest = tmp_dir.child("Cppargo.toml");
+            project_manifest.write_str(concat!(
+                "[project]\nname = \"foo\"\n",
+                "[build]\n",
+                "sources = [\"src/**/*.cpp\"]\n",
+                "exclude = [\"src/experimental/**\"]\n"
+            ))?;
+
+            let manifest = read(project_manifest.path())?;
+
+            anyhow::ensure!(
+                manifest.build.sources == Some(vec!["src/**/*.cpp".to_string()]),
+                format!("Got unexpected sources {:?}!", manifest.build.sources)
+            );
+            anyhow::ensure!(
+                manifest.build.exclude == vec!["src/experimental/**".to_string()],
+                format!("Got unexpected exclude {:?}!", manifest.build.exclude)
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn target_triples() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_manifest = tmp_dir.child("Cppargo.toml");
+            project_manifest.write_str(concat!(
+                "[project]\nname = \"foo\"\n",
+                "[target.aarch64-linux-gnu]\n",
+                "sysroot = \"/usr/aarch64-linux-gnu\"\n",
+                "flags = [\"-mcpu=cortex-a72\"]\n",
+                "runner = [\"qemu-aarch64\", \"-L\", \"/usr/aarch64-linux-gnu\"]\n",
+                "[target.'cfg(unix)'.build]\n",
+                "exclude = [\"src/windows/**\"]\n"
+            ))?;
+
+            let manifest = read(project_manifest.path())?;
+            let expected = TripleConfig {
+                sysroot: Some("/usr/aarch64-linux-gnu".to_string()),
+                flags: vec!["-mcpu=cortex-a72".to_string()],
+                runner: Some(
+                    ["qemu-aarch64", "-L", "/usr/aarch64-linux-gnu"]
+                        .map(str::to_string)
+                        .to_vec(),
+                ),
+                ..Default::default()
+            };
+
+            anyhow::ensure!(
+                manifest.triples.get("aarch64-linux-gnu") == Some(&expected),
+                format!("Got unexpected triples {:?}!", manifest.triples)
+            );
+            anyhow::ensure!(
+                manifest.targets.len() == 1,
+                "Failed to read cfg target alongside triples!"
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn workspace() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let workspace_manifest = tmp_dir.child("Cppargo.toml");
+            workspace_manifest.write_str("[workspace]\nmembers = [\"libs/*\", \"apps/*\"]\n")?;
+            let project_manifest = tmp_dir.child("foo").child("Cppargo.toml");
+            project_manifest.write_str(PROJECT_MANIFEST)?;
+
+            let workspace = read_workspace(workspace_manifest.path())?;
+            let expected = WorkspaceConfig {
+                members: vec!["libs/*".to_string(), "apps/*".to_string()],
+                root_project: false,
+            };
+            anyhow::ensure!(
+                workspace.as_ref() == Some(&expected),
+                format!("Got unexpected workspace {workspace:?}!")
+            );
+            anyhow::ensure!(
+                read_workspace(project_manifest.path())?.is_none(),
+                "Found a workspace in a project manifest!"
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn run_runner_and_env() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_manifest = tmp_dir.child("Cppargo.toml");
+            project_manifest.write_str(concat!(
+                "[project]\nname = \"foo\"\n",
+                "[run]\n",
+                "runner = [\"valgrind\", \"--leak-check=full\"]\n",
+                "[run.env]\n",
+                "LOG_LEVEL = \"debug\"\n"
+            ))?;
+
+            let run = read(project_manifest.path())?.run;
+            let expected = RunConfig {
+                runner: Some(vec![
+                    "valgrind".to_string(),
+                    "--leak-check=full".to_string(),
+                ]),
+                env: BTreeMap::from([("LOG_LEVEL".to_string(), "debug".to_string())]),
+            };
+
+            anyhow::ensure!(
+                run == expected,
+                format!("Got unexpected run config {run:?}!")
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn fail_invalid_run() -> anyhow::Result<()> {
+            for run in [
+                "[run]\nrunner = []\n",
+                "[run]\nrunner = \"valgrind\"\n",
+                "[run.env]\nLOG_LEVEL = 1\n",
+            ] {
+                let tmp_dir = assert_fs::TempDir::new()?;
+                let project_manifest = tmp_dir.child("Cppargo.toml");
+                project_manifest.write_str(&format!("{PROJECT_MANIFEST}{run}"))?;
+
+                anyhow::ensure!(
+                    read(project_manifest.path()).is_err(),
+                    format!("Accepted invalid run config:\n{run}")
+                );
+            }
+
+            Ok(())
+        }
+
+        #[test]
+        fn fail_invalid_target_cfg() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_manifest = tmp_dir.child("Cppargo.toml");
+            project_manifest.write_str(concat!(
+                "[project]\nname = \"foo\"\n",
+                "[target.'cfg(unix'.build]\n",
+                "sources = [\"src/unix/*.cpp\"]\n"
+            ))?;
+
+            anyhow::ensure!(
+                read(project_manifest.path()).is_err(),
+                "Accepted an invalid target cfg!"
+            );
+
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod lints {
+        use super::*;
+
+        #[test]
+        fn succeed() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_manifest = tmp_dir.child("Cppargo.toml");
+            project_manifest.write_str(concat!(
+                "[project]\nname = \"foo\"\n",
+                "[lints]\n",
+                "level = \"pedantic\"\n",
+                "flags = [\"-Wshadow\"]\n",
+                "deny-warnings = true\n"
+            ))?;
+
+            let lints = read(project_manifest.path())?.lints;
+
+            anyhow::ensure!(
+                lints.level == WarningLevel::Pedantic
+                    && lints.flags == ["-Wshadow"]
+                    && lints.deny_warnings,
+                "Failed to read lints from manifest!"
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn fail_invalid_level() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_manifest = tmp_dir.child("Cppargo.toml");
+            project_manifest.write_str("[project]\nname = \"foo\"\n[lints]\nlevel = \"most\"\n")?;
+
+            anyhow::ensure!(
+                read(project_manifest.path()).is_err(),
+                "Accepted an invalid warning level!"
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn fail_non_warning_flag() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_manifest = tmp_dir.child("Cppargo.toml");
+            project_manifest
+                .write_str("[project]\nname = \"foo\"\n[lints]\nflags = [\"-O3\"]\n")?;
+
+            anyhow::ensure!(
+                read(project_manifest.path()).is_err(),
+                "Accepted a non-warning lint flag!"
+            );
+
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod profiles {
+        use super::*;
+
+        #[test]
+        fn default_profiles() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_manifest = tmp_dir.child("Cppargo.toml");
+            project_manifest.write_str(PROJECT_MANIFEST)?;
+
+            let profiles = read(project_manifest.path())?.profiles;
+
+            anyhow::ensure!(
+                profiles.dev == ProfileConfig::default_for(Profile::Dev)
+                    && profiles.release == ProfileConfig::default_for(Profile::Release),
+                "Got unexpected default profiles!"
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn override_defaults() -> anyhow::Result<()> {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_manifest = tmp_dir.child("Cppargo.toml");
+            project_manifest.write_str(concat!(
+                "[project]\nname = \"foo\"\n",
+                "[profile.dev]\n",
+                "opt-level = 1\n",
+                "debug = true\n",
+                "split-debuginfo = true\n",
+                "[profile.release]\n",
+                "opt-level = \"s\"\n",
+                "debug-assertions = true\n",
+                "lto = \"thin\"\n",
+                "linker = \"mold\"\n",
+                "strip = \"symbols\"\n"
+            ))?;
+
+            let profiles = read(project_manifest.path())?.profiles;
+
+            anyhow::ensure!(
+                profiles.dev
+                    == ProfileConfig {
+                        opt_level: Some("1".to_string()),
+                        debug: true,
+                        debug_assertions: true,
+                        lto: Lto::Off,
+                        linker: None,
+                        strip: Strip::None,
+                        split_debuginfo: true,
+                    },
+                format!("Got unexpected dev profile {:?}!", profiles.dev)
+            );
+            anyhow::ensure!(
+                profiles.release
+                    == ProfileConfig {
+                        opt_level: Some("s".to_string()),
+                        debug: false,
+                        debug_assertions: true,
+                        lto: Lto::Thin,
+                        linker: Some(Linker::Mold),
+                        strip: Strip::Symbols,
+                        split_debuginfo: false,
+                    },
+                format!("Got unexpected release profile {:?}!", profiles.release)
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn fail_invalid_profiles() -> anyhow::Result<()> {
+            for profiles in [
+                "[profile.fast]\nopt-level = 3\n",
+                "[profile.release]\nopt-level = 4\n",
+                "[profile.release]\ndebug = \"yes\"\n",
+                "[profile.release]\nlto = true\n",
+                "[profile.release]\nlto = \"fat\"\n",
+                "[profile.release]\nlinker = \"ld\"\n",
+                "[profile.release]\nstrip = true\n",
+                "[profile.release]\nstrip = \"all\"\n",
+                "[profile.release]\nsplit-debuginfo = true\n",
+            ] {
+                let tmp_dir = assert_fs::TempDir::new()?;
+                let project_manifest = tmp_dir.child("Cppargo.toml");
+                project_manifest.write_str(&format!("{PROJECT_MANIFEST}{profiles}"))?;
+
+                anyhow::ensure!(
+                    read(project_manifest.path()).is_err(),
+                    format!("Accepted invalid profiles:\n{profiles}")
+                );
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn merge_matching_target_build_configs() -> anyhow::Result<()> {
+        let tmp_dir = assert_fs::TempDir::new()?;
+        let project_manifest = tmp_dir.child("Cppargo.toml");
+        project_manifest.write_str(concat!(
+            "[project]\nname = \"foo\"\n",
+            "[build]\n",
+            "sources = [\"src/*.cpp\"]\n",
+            "[target.'cfg(target_os = \"linux\")'.build]\n",
+            "sources = [\"src/linux/*.cpp\"]\n",
+            "[target.'cfg(windows)'.build]\n",
+            "sources = [\"src/windows/*.cpp\"]\n",
+            "exclude = [\"src/main.cpp\"]\n"
+        ))?;
+
+        let manifest = read(project_manifest.path())?;
+        let build = manifest.build_config(&cfg::Platform {
+            os: "linux".to_string(),
+            family: "unix".to_string(),
+            arch: "x86_64".to_string(),
+        });
+
+        anyhow::ensure!(
+            build.sources == Some(vec!["src/*.cpp".to_string(), "src/linux/*.cpp".to_string()]),
+            format!("Got unexpected sources {:?}!", build.sources)
+        );
+        anyhow::ensure!(
+            build.exclude.is_empty(),
+            format!("Got unexpected exclude {:?}!", build.exclude)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn build_script() -> anyhow::Result<()> {
+        for (script, expected) in [
+            (
+                "\"tools/gen.cpp\"",
+                BuildScript::Source(PathBuf::from("tools/gen.cpp")),
+            ),
+            (
+                "[\"python3\", \"gen.py\"]",
+                BuildScript::Command(vec!["python3".to_string(), "gen.py".to_string()]),
+            ),
+        ] {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_manifest = tmp_dir.child("Cppargo.toml");
+            project_manifest
+                .write_str(&format!("{PROJECT_MANIFEST}[build]\nscript = {script}\n"))?;
+
+            let manifest = read(project_manifest.path())?;
+            anyhow::ensure!(
+                manifest.build.script.as_ref() == Some(&expected),
+                format!("Got unexpected build script {:?}!", manifest.build.script)
+            );
+        }
+
+        for script in ["[]", "1"] {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_manifest = tmp_dir.child("Cppargo.toml");
+            project_manifest
+                .write_str(&format!("{PROJECT_MANIFEST}[build]\nscript = {script}\n"))?;
+
+            anyhow::ensure!(
+                read(project_manifest.path()).is_err(),
+                format!("Accepted invalid build script {script}!")
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn compiler_launcher() -> anyhow::Result<()> {
+        for (launcher, expected) in [
+            ("\"ccache\"", vec!["ccache"]),
+            ("\"sccache --foo\"", vec!["sccache", "--foo"]),
+            ("[\"ccache\"]", vec!["ccache"]),
+        ] {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_manifest = tmp_dir.child("Cppargo.toml");
+            project_manifest.write_str(&format!(
+                "{PROJECT_MANIFEST}[build]\ncompiler-launcher = {launcher}\n"
+            ))?;
+
+            let manifest = read(project_manifest.path())?;
+            anyhow::ensure!(
+                manifest.build.compiler_launcher
+                    == Some(expected.iter().map(ToString::to_string).collect()),
+                format!(
+                    "Got unexpected compiler launcher {:?}!",
+                    manifest.build.compiler_launcher
+                )
+            );
+        }
+
+        for launcher in ["\"\"", "[]", "1"] {
+            let tmp_dir = assert_fs::TempDir::new()?;
+            let project_manifest = tmp_dir.child("Cppargo.toml");
+            project_manifest.write_str(&format!(
+                "{PROJECT_MANIFEST}[build]\ncompiler-launcher = {launcher}\n"
+            ))?;
+
+            anyhow::ensure!(
+                read(project_manifest.path()).is_err(),
+                format!("Accepted invalid compiler launcher {launcher}!")
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn fail_target_sources_without_build_sources() -> anyhow::Result<()> {
+        let tmp_dir = assert_fs::TempDir::new()?;
+        let project_manifest = tmp_dir.child("Cppargo.toml");
+        // Rejected on every platform, not only on the matching ones.
+        project_manifest.write_str(concat!(
+            "[project]\nname = \"foo\"\n",
+            "[target.'cfg(windows)'.build]\n",
+            "sources = [\"vendor/windows.c\"]\n"
+        ))?;
+
+        anyhow::ensure!(
+            read(project_manifest.path()).is_err(),
+            "Accepted target sources without `[build] sources`!"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn target_sources_extend_empty_build_sources() -> anyhow::Result<()> {
+        let tmp_dir = assert_fs::TempDir::new()?;
+        let project_manifest = tmp_dir.child("Cppargo.toml");
+        project_manifest.write_str(concat!(
+            "[project]\nname = \"foo\"\n",
+            "[build]\n",
+            "sources = []\n",
+            "[target.'cfg(unix)'.build]\n",
+            "sources = [\"src/unix/**\"]\n",
+            "[target.'cfg(target_arch = \"x86_64\")'.build]\n",
+            "sources = [\"vendor/x86_64.c\"]\n"
+        ))?;
+
+        let manifest = read(project_manifest.path())?;
+        let build = manifest.build_config(&cfg::Platform {
+            os: "linux".to_string(),
+            family: "unix".to_string(),
+            arch: "x86_64".to_string(),
+        });
+
+        // Only the sources of the matching tables are compiled.
+        anyhow::ensure!(
+            build.sources
+                == Some(vec![
+                    "src/unix/**".to_string(),
+                    "vendor/x86_64.c".to_string()
+                ]),
+            format!("Got unexpected sources {:?}!", build.sources)
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+use crate::{
+    diagnostic::{self, CompilerOutput, Diagnostic, Output, Severity},
+    Context,
+};
+use std::{cell::RefCell, fs, path::Path, process::Command};
+
+/// How progress and results of a build are reported.
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum MessageFormat {
+    /// Human readable progress lines.
+    #[default]
+    Human,
+    /// Newline-delimited JSON events on stdout.
+    Json,
+}
+
+/// Reports build progress either as human readable lines, or as one JSON
+/// object per line with a `reason` field naming the event.
+///
+/// Every reported compiler diagnostic is also kept, to summarize them once the
+/// build finishes.
+pub struct Reporter {
+    format: MessageFormat,
+    diagnostics: RefCell<Vec<Diagnostic>>,
+}
+
+impl Reporter {
+    pub fn new(format: MessageFormat) -> Self {
+        Self {
+            format,
+            diagnostics: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// A build is about to start, which only checks the project if `check`.
+    pub fn build_started(&self, check: bool) {
+        match self.format {
+            MessageFormat::Human if check => println!("Checking project..."),
+            MessageFormat::Human => println!("Building project..."),
+            MessageFormat::Json => emit(&serde_json::json!({ "reason": "build-started" })),
+        }
+    }
+
+    /// A compiler (or linker, if `src_file` is `None`) is about to be run.
+    pub fn compiler_invocation(&self, command: &Command, src_file: Option<&Path>) {
+        match self.format {
+            MessageFormat::Human => match src_file {
+                Some(_) => println!("Running compiler...\n{command:?}"),
+                None => println!("Running linker...\n{command:?}"),
+            },
+            MessageFormat::Json => {
+                let arguments: Vec<_> = command
+                    .get_args()
+                    .map(|arg| arg.to_string_lossy())
+                    .collect();
+                emit(&serde_json::json!({
+                    "reason": "compiler-invocation",
+                    "program": command.get_program().to_string_lossy(),
+                    "arguments": arguments,
+                    "file": src_file.map(Path::to_string_lossy),
+                }));
+            }
+        }
+    }
+
+    /// Output the compiler printed while compiling `src_file`, or while linking
+    /// if `src_file` is `None`.
+    pub fn compiler_output(&self, src_file: Option<&Path>, output: CompilerOutput) {
+        let source = src_file.map(Path::to_string_lossy);
+
+        for part in output.parts {
+            match part {
+                Output::Diagnostic(diagnostic) => {
+                    match self.format {
+                        MessageFormat::Human => eprint!("{diagnostic}"),
+                        MessageFormat::Json => {
+                            let mut event = diagnostic.to_json();
+                            event["reason"] = "diagnostic".into();
+                            event["source"] = serde_json::json!(source);
+                            event["rendered"] = diagnostic.to_string().into();
+                            emit(&event);
+                        }
+                    }
+                    self.diagnostics.borrow_mut().push(diagnostic);
+                }
+                Output::Other(lines) => {
+                    let rendered = lines.join("\n") + "\n";
+                    match self.format {
+                        MessageFormat::Human => eprint!("{rendered}"),
+                        MessageFormat::Json => emit(&serde_json::json!({
+                            "reason": "compiler-output",
+                            "source": source,
+                            "rendered": rendered,
+                        })),
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn artifact(&self, path: &Path) {
+        if let MessageFormat::Json = self.format {
+            emit(&serde_json::json!({
+                "reason": "artifact",
+                "path": path.to_string_lossy(),
+            }));
+        }
+    }
+
+    /// A build, which only checked the project if `check`, finished.
+    pub fn build_finished(&self, success: bool, check: bool) {
+        let count = |severity| {
+            self.diagnostics
+                .borrow()
+                .iter()
+                .filter(|d| d.severity == severity)
+                .count()
+        };
+        let (warnings, errors) = (count(Severity::Warning), count(Severity::Error));
+
+        match self.format {
+            MessageFormat::Human => {
+                if warnings + errors > 0 {
+                    eprintln!(
+                        "Build finished with {warnings} warning{} and {errors} error{}.",
+                        if warnings == 1 { "" } else { "s" },
+                        if errors == 1 { "" } else { "s" },
+                    );
+                }
+                if success {
+                    println!(
+                        "Project {} successfully!",
+                        if check { "checked" } else { "built" }
+                    );
+                }
+            }
+            MessageFormat::Json => emit(&serde_json::json!({
+                "reason": "build-finished",
+                "success": success,
+                "warnings": warnings,
+                "errors": errors,
+            })),
+        }
+    }
+
+    /// Write every reported diagnostic as a SARIF log at `path`.
+    pub fn write_sarif(&self, path: &Path) -> anyhow::Result<()> {
+        let log = diagnostic::sarif(&self.diagnostics.borrow());
+        fs::write(path, serde_json::to_string_pretty(&log)? + "\n")
+            .with_context(|| format!("Failed to write SARIF log at {}!", path.display()))?;
+
+        Ok(())
+    }
+
+    /// Print a human readable progress line, which has no JSON equivalent.
+    pub fn status(&self, message: &str) {
+        if let MessageFormat::Human = self.format {
+            println!("{message}");
+        }
+    }
+}
+
+fn emit(event: &serde_json::Value) {
+    println!("{event}");
+}
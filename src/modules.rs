@@ -0,0 +1,530 @@
+use crate::{build, Context};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Extensions of C++20 module interface units, which compilers don't
+/// recognize as C++ on their own.
+pub const INTERFACE_EXTENSIONS: [&str; 2] = ["cppm", "ixx"];
+
+/// The C++20 module declarations of a source file.
+#[derive(Debug, Default, PartialEq)]
+struct Declarations {
+    /// Module, or module partition, exported by an interface unit, or by a
+    /// partition implementation unit.
+    exports: Option<String>,
+    /// Modules imported, or implemented, by the source file.
+    imports: BTreeSet<String>,
+    /// Whether the source file belongs to a named module.
+    unit: bool,
+}
+
+/// The C++20 modules of a project's source files.
+pub struct Modules {
+    /// Every source file, ordered so that the interface unit of a module is
+    /// compiled before any source file importing it.
+    pub build_order: Vec<PathBuf>,
+    /// Interface units, by source file, with the module they export.
+    pub interfaces: BTreeMap<PathBuf, String>,
+    /// Source files belonging to a named module, whose module declaration
+    /// has to come before anything else they contain.
+    pub units: BTreeSet<PathBuf>,
+}
+
+/// Where the compiled interfaces of a project's modules are stored, and how
+/// they are found when compiling the files importing them.
+pub struct Interfaces {
+    /// Interface units, by source file, with the module they export.
+    pub modules: BTreeMap<PathBuf, String>,
+    /// Directory of the compiled interfaces, relative to the project root.
+    pub dir: PathBuf,
+    /// Whether interfaces are precompiled into `.pcm` files apart from their
+    /// object files, as by Clang, instead of along with them as `.gcm` files,
+    /// as by GCC.
+    pub precompiled: bool,
+}
+
+impl Interfaces {
+    /// The compiled interface of `module`.
+    pub fn file(&self, module: &str) -> PathBuf {
+        let extension = if self.precompiled { "pcm" } else { "gcm" };
+        // Clang looks partitions up as `MODULE-PARTITION.pcm`.
+        self.dir
+            .join(format!("{}.{extension}", module.replace(':', "-")))
+    }
+
+    /// Flags to compile every C++ source file with, writing the module
+    /// mapper GCC reads the paths of the compiled interfaces from into
+    /// `project_root`.
+    pub fn flags(&self, project_root: &Path) -> anyhow::Result<Vec<String>> {
+        fs::create_dir_all(project_root.join(&self.dir))
+            .with_context(|| format!("Failed to create directory {}!", self.dir.display()))?;
+
+        if self.precompiled {
+            return Ok(vec![
+                "-std=c++20".to_string(),
+                format!("-fprebuilt-module-path={}", self.dir.display()),
+            ]);
+        }
+
+        let mapper = self.dir.join("modules.map");
+        let mut mappings: Vec<_> = self
+            .modules
+            .values()
+            .map(|module| format!("{module} {}\n", self.file(module).display()))
+            .collect();
+        mappings.sort();
+        fs::write(project_root.join(&mapper), mappings.concat())
+            .with_context(|| format!("Failed to write module mapper {}!", mapper.display()))?;
+
+        Ok(vec![
+            "-fmodules-ts".to_string(),
+            format!("-fmodule-mapper={}", mapper.display()),
+        ])
+    }
+}
+
+/// Whether `src_file` is a module interface unit, judging by its extension.
+pub fn is_interface_file(src_file: &Path) -> bool {
+    src_file
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| INTERFACE_EXTENSIONS.contains(&ext))
+}
+
+/// Whether `src_file` is C++, and so may declare modules, unlike C, where
+/// `module` and `import` are ordinary identifiers.
+fn is_cpp_file(src_file: &Path) -> bool {
+    src_file
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| build::CPP_EXTENSIONS.contains(&ext))
+        || is_interface_file(src_file)
+}
+
+/// Scan the C++ `src_files` of the project at `project_root` for the modules
+/// they export and import, to find the order they have to be compiled in.
+pub fn scan(project_root: &Path, src_files: &BTreeSet<PathBuf>) -> anyhow::Result<Modules> {
+    let mut declarations = BTreeMap::new();
+    for src_file in src_files {
+        if !is_cpp_file(src_file) {
+            declarations.insert(src_file.clone(), Declarations::default());
+            continue;
+        }
+        let source = fs::read(project_root.join(src_file))
+            .with_context(|| format!("Failed to read {}!", src_file.display()))?;
+        let file_declarations = parse(&String::from_utf8_lossy(&source))
+            .with_context(|| format!("Invalid module declarations in {}!", src_file.display()))?;
+        declarations.insert(src_file.clone(), file_declarations);
+    }
+
+    let mut interfaces = BTreeMap::new();
+    let mut exporters: BTreeMap<&str, &PathBuf> = BTreeMap::new();
+    for (src_file, file_declarations) in &declarations {
+        let Some(module) = &file_declarations.exports else {
+            continue;
+        };
+        if let Some(other_file) = exporters.insert(module, src_file) {
+            anyhow::bail!(format!(
+                "Module `{module}` is exported by both {} and {}!",
+                other_file.display(),
+                src_file.display()
+            ));
+        }
+        interfaces.insert(src_file.clone(), module.clone());
+    }
+
+    let units = declarations
+        .iter()
+        .filter(|(_, file_declarations)| file_declarations.unit)
+        .map(|(src_file, _)| src_file.clone())
+        .collect();
+
+    let mut dependencies = BTreeMap::new();
+    for (src_file, file_declarations) in &declarations {
+        let mut file_dependencies = BTreeSet::new();
+        for module in &file_declarations.imports {
+            let Some(exporter) = exporters.get(module.as_str()) else {
+                anyhow::bail!(format!(
+                    "Module `{module}` imported by {} isn't exported by any source file!",
+                    src_file.display()
+                ));
+            };
+            file_dependencies.insert((*exporter).clone());
+        }
+        dependencies.insert(src_file.clone(), file_dependencies);
+    }
+
+    Ok(Modules {
+        build_order: build_order(dependencies)?,
+        interfaces,
+        units,
+    })
+}
+
+/// Order the source files so that each comes after its `dependencies`, and
+/// otherwise by path, so that the compiler is always invoked in the same
+/// order.
+fn build_order(
+    mut dependencies: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut build_order = Vec::with_capacity(dependencies.len());
+    while let Some(src_file) = dependencies
+        .iter()
+        .find(|(_, file_dependencies)| file_dependencies.is_empty())
+        .map(|(src_file, _)| src_file.clone())
+    {
+        dependencies.remove(&src_file);
+        for file_dependencies in dependencies.values_mut() {
+            file_dependencies.remove(&src_file);
+        }
+        build_order.push(src_file);
+    }
+
+    anyhow::ensure!(
+        dependencies.is_empty(),
+        format!(
+            "The modules of {} import each other in a cycle!",
+            dependencies
+                .keys()
+                .map(|src_file| src_file.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    );
+
+    Ok(build_order)
+}
+
+/// Parse the module declarations of a `source` file, which have to be on
+/// lines of their own.
+///
+/// Lines which aren't a keyword followed by a module name and `;` are
+/// ignored, since `module` and `import` are also ordinary identifiers, as in
+/// `module = 3;`. So are comments and the contents of string literals.
+fn parse(source: &str) -> anyhow::Result<Declarations> {
+    let mut declarations = Declarations::default();
+    // Module the source file belongs to, which partitions are imported from.
+    let mut module = None;
+
+    for line in strip_comments(source).lines() {
+        let line = line.trim();
+        let (exported, declaration) = match keyword(line, "export") {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let Some((declaration, rest)) = declaration.split_once(';') else {
+            continue;
+        };
+        let rest = rest.trim_start();
+        if !rest.is_empty() && !rest.starts_with("//") {
+            continue;
+        }
+        let declaration = declaration.trim_end();
+
+        if let Some(name) = keyword(declaration, "module") {
+            // Neither the global module fragment nor the private one belong
+            // to a named module.
+            if name.is_empty() || name == ":private" || !is_module_name(name) {
+                continue;
+            }
+            let base = name.split(':').next().unwrap_or(name);
+            module = Some(base.to_string());
+            declarations.unit = true;
+            if exported || name.contains(':') {
+                declarations.exports = Some(name.to_string());
+            } else {
+                declarations.imports.insert(name.to_string());
+            }
+        } else if let Some(name) = keyword(declaration, "import") {
+            if name.starts_with(['<', '"']) {
+                anyhow::bail!(format!(
+                    "Importing header units, such as with `{line}`, isn't supported!"
+                ));
+            }
+            let partition = name.strip_prefix(':');
+            if !partition.map_or_else(|| is_module_name(name), is_identifier) {
+                continue;
+            }
+            let name = match (partition, &module) {
+                (Some(partition), Some(module)) => format!("{module}:{partition}"),
+                (Some(_), None) => anyhow::bail!(format!(
+                    "Partition imported with `{line}` outside of a module!"
+                )),
+                (None, _) => name.to_string(),
+            };
+            declarations.imports.insert(name);
+        }
+    }
+
+    Ok(declarations)
+}
+
+/// The `source` without its comments, and with its string literals emptied,
+/// keeping its lines where they were.
+fn strip_comments(source: &str) -> String {
+    let mut stripped = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    // Identifier or number right before the current character, which tells
+    // the prefix of a raw string literal, such as `R"(...)"`, apart from a
+    // digit separator, such as in `1'000`.
+    let mut word = String::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'/') => while chars.next_if(|&c| c != '\n').is_some() {},
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    if c == '\n' {
+                        stripped.push('\n');
+                    }
+                    previous = c;
+                }
+                stripped.push(' ');
+            }
+            '"' if ["R", "u8R", "uR", "UR", "LR"].contains(&word.as_str()) => {
+                let mut delimiter = String::from(")");
+                delimiter.extend(chars.by_ref().take_while(|&c| c != '('));
+                delimiter.push('"');
+                let mut contents = String::new();
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        stripped.push('\n');
+                    }
+                    contents.push(c);
+                    if contents.ends_with(&delimiter) {
+                        break;
+                    }
+                }
+                stripped.push_str("\"\"");
+            }
+            '"' | '\'' if c == '"' || ["", "u8", "u", "U", "L"].contains(&word.as_str()) => {
+                stripped.push(c);
+                while let Some(next) = chars.next_if(|&next| next != '\n') {
+                    if next == '\\' {
+                        chars.next_if(|&next| next != '\n');
+                    } else if next == c {
+                        stripped.push(c);
+                        break;
+                    }
+                }
+            }
+            c => stripped.push(c),
+        }
+
+        if c.is_alphanumeric() || c == '_' {
+            word.push(c);
+        } else {
+            word.clear();
+        }
+    }
+
+    stripped
+}
+
+/// The rest of `line` if it starts with the `keyword`, followed by anything
+/// that can't continue an identifier.
+fn keyword<'a>(line: &'a str, keyword: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix(keyword)?;
+    if rest.starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+
+    Some(rest.trim_start())
+}
+
+/// Whether `name` is a module name, such as `math.core`, optionally followed
+/// by a `:partition`.
+fn is_module_name(name: &str) -> bool {
+    match name.split_once(':') {
+        Some((module, partition)) => is_identifier(module) && is_identifier(partition),
+        None => is_identifier(name),
+    }
+}
+
+/// Whether `name` is a dot separated identifier, such as `math.core`.
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+
+    #[test]
+    fn proper_parse() -> anyhow::Result<()> {
+        for (source, exports, imports, unit) in [
+            (
+                "module;\n#include <string>\nexport module math;\nexport import :ops;\nimport util;\n",
+                Some("math"),
+                vec!["math:ops", "util"],
+                true,
+            ),
+            ("export module math:ops;\n", Some("math:ops"), vec![], true),
+            ("module math:detail;\nimport :ops;\n", Some("math:detail"), vec!["math:ops"], true),
+            ("module math;\nmodule :private;\n", None, vec!["math"], true),
+            ("// import commented;\nint important = 1;\nimport math ;\n", None, vec!["math"], false),
+            (
+                "int module;\nmodule = 3;\nimport = module + 1;\nimport\n    = 2;\nmodule(x);\n",
+                None,
+                vec![],
+                false,
+            ),
+            ("import math; // for sqrt\nimport util; int x;\n", None, vec!["math"], false),
+            (
+                "/*\nimport old;\n*/\n/* import older; */ import math; /* for sqrt */\n",
+                None,
+                vec!["math"],
+                false,
+            ),
+            (
+                "auto help = R\"usage(\nimport foo;\n)usage\";\nint n = 1'000;\nimport math;\n",
+                None,
+                vec!["math"],
+                false,
+            ),
+            (
+                "auto glob = \"src/*.cpp\";\nchar quote = '\"';\nimport math;\n",
+                None,
+                vec!["math"],
+                false,
+            ),
+        ] {
+            let declarations = parse(source)?;
+            let expected = Declarations {
+                exports: exports.map(ToString::to_string),
+                imports: imports.into_iter().map(ToString::to_string).collect(),
+                unit,
+            };
+            anyhow::ensure!(
+                declarations == expected,
+                format!("Got unexpected declarations {declarations:?} of:\n{source}")
+            );
+        }
+
+        anyhow::ensure!(
+            parse("import <iostream>;\n").is_err(),
+            "Accepted a header unit import!"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn proper_build_order() -> anyhow::Result<()> {
+        let project_root = assert_fs::TempDir::new()?;
+        for (src_file, source) in [
+            ("src/a.cpp", "import math;\n"),
+            ("src/main.cpp", "import app;\n"),
+            (
+                "src/math.cppm",
+                "export module math;\nexport import :ops;\n",
+            ),
+            ("src/ops.cppm", "export module math:ops;\n"),
+            ("src/app.ixx", "export module app;\nimport math;\n"),
+        ] {
+            project_root.child(src_file).write_str(source)?;
+        }
+        let src_files = [
+            "src/a.cpp",
+            "src/main.cpp",
+            "src/math.cppm",
+            "src/ops.cppm",
+            "src/app.ixx",
+        ]
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+
+        let modules = scan(&project_root, &src_files)?;
+        anyhow::ensure!(
+            modules.build_order
+                == [
+                    "src/ops.cppm",
+                    "src/math.cppm",
+                    "src/a.cpp",
+                    "src/app.ixx",
+                    "src/main.cpp"
+                ]
+                .map(PathBuf::from),
+            format!("Got unexpected build order {:?}!", modules.build_order)
+        );
+        anyhow::ensure!(
+            modules.interfaces.len() == 3
+                && modules.interfaces[Path::new("src/ops.cppm")] == "math:ops",
+            format!("Got unexpected interfaces {:?}!", modules.interfaces)
+        );
+        anyhow::ensure!(
+            modules.units.len() == 3 && !modules.units.contains(Path::new("src/a.cpp")),
+            format!("Got unexpected module units {:?}!", modules.units)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn ignore_identifiers_named_like_keywords() -> anyhow::Result<()> {
+        let project_root = assert_fs::TempDir::new()?;
+        project_root
+            .child("src/main.cpp")
+            .write_str("int module;\nint main() {\n    module = 3;\n}\n")?;
+        project_root
+            .child("src/legacy.c")
+            .write_str("int import;\nimport legacy;\nmodule legacy;\n")?;
+        let src_files = ["src/legacy.c", "src/main.cpp"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+
+        let modules = scan(&project_root, &src_files)?;
+        anyhow::ensure!(
+            modules.interfaces.is_empty()
+                && modules.build_order == ["src/legacy.c", "src/main.cpp"].map(PathBuf::from),
+            format!("Got unexpected build order {:?}!", modules.build_order)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn fail_invalid_modules() -> anyhow::Result<()> {
+        for sources in [
+            vec![("src/main.cpp", "import math;\n")],
+            vec![
+                ("src/a.cppm", "export module math;\n"),
+                ("src/b.cppm", "export module math;\n"),
+            ],
+            vec![
+                ("src/a.cppm", "export module a;\nimport b;\n"),
+                ("src/b.cppm", "export module b;\nimport a;\n"),
+            ],
+        ] {
+            let project_root = assert_fs::TempDir::new()?;
+            for (src_file, source) in &sources {
+                project_root.child(src_file).write_str(source)?;
+            }
+            let src_files = sources
+                .iter()
+                .map(|(src_file, _)| PathBuf::from(src_file))
+                .collect();
+
+            anyhow::ensure!(
+                scan(&project_root, &src_files).is_err(),
+                format!("Accepted invalid modules {sources:?}!")
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -17,13 +17,22 @@ const HELLO_WORLD_PROGRAM: &str = concat!(
     "}\n"
 );
 
-pub fn main(path: &Path) -> anyhow::Result<()> {
+/// Version of newly created projects.
+const INITIAL_VERSION: &str = "0.1.0";
+
+pub fn main(path: &Path, name: Option<&str>) -> anyhow::Result<()> {
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => project_name_from_path(path)?,
+    };
+    validate_project_name(&name)?;
+
     let project_root: PathBuf =
         create_project_fs(path).with_context(|| "Failed to create project file structure")?;
 
     initialize_vcs(&project_root)?;
 
-    create_manifest(&project_root)?;
+    create_manifest(&project_root, &name)?;
 
     create_hello_world(&project_root.join("src"))
         .with_context(|| "Failed to create simple 'Hello World!' program.")?;
@@ -74,21 +83,75 @@ fn initialize_vcs(project_root: &Path) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn create_manifest(project_root: &Path) -> anyhow::Result<()> {
-    let name = match project_root.file_name() {
+fn project_name_from_path(path: &Path) -> anyhow::Result<String> {
+    let name = match path.file_name() {
         Some(osstr) => match osstr.to_str() {
             Some(str) => str.to_string(),
             None => anyhow::bail!(format!("Failed to convert project name to str {osstr:?}!")),
         },
         None => anyhow::bail!(format!(
             "Failed to get project name from project root: {}!",
-            project_root.display()
+            path.display()
         )),
     };
 
+    Ok(name)
+}
+
+/// Ensure `name` is usable as a project (and therefore binary) name.
+///
+/// A valid name is non-empty, starts with an ASCII letter or `_`, and
+/// otherwise only contains ASCII letters, digits, `_` or `-`.
+fn validate_project_name(name: &str) -> anyhow::Result<()> {
+    let problem = match name.chars().next() {
+        None => Some("it is empty".to_string()),
+        Some(first) if !(first.is_ascii_alphabetic() || first == '_') => Some(format!(
+            "it must start with an ASCII letter or `_`, not `{first}`"
+        )),
+        Some(_) => name
+            .chars()
+            .find(|c| !is_valid_name_char(*c))
+            .map(|c| format!("it contains the invalid character `{c}`")),
+    };
+
+    if let Some(problem) = problem {
+        let suggestion = sanitize_project_name(name);
+        anyhow::bail!(format!(
+            concat!(
+                "Invalid project name `{}`: {}! ",
+                "Use `--name {}` to set a valid name instead."
+            ),
+            name, problem, suggestion
+        ));
+    }
+
+    Ok(())
+}
+
+fn is_valid_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Turn `name` into a valid project name by replacing every invalid character
+/// with `_` and prefixing it with `_` if it doesn't start with a letter.
+fn sanitize_project_name(name: &str) -> String {
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| if is_valid_name_char(c) { c } else { '_' })
+        .collect();
+
+    if !sanitized.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        sanitized.insert(0, '_');
+    }
+
+    sanitized
+}
+
+fn create_manifest(project_root: &Path, name: &str) -> anyhow::Result<()> {
     let mut manifest = toml_edit::DocumentMut::new();
     manifest["project"] = toml_edit::Item::Table(toml_edit::Table::new());
     manifest["project"]["name"] = toml_edit::value(name);
+    manifest["project"]["version"] = toml_edit::value(INITIAL_VERSION);
 
     let manifest_path = project_root.join("Cppargo.toml");
     fs::write(&manifest_path, manifest.to_string())
@@ -152,9 +215,13 @@ mod tests {
         project_root.create_dir_all()?;
         let project_manifest = project_root.child("Cppargo.toml");
 
-        create_manifest(project_root.path())?;
+        create_manifest(project_root.path(), "foo")?;
 
-        project_manifest.assert(concat!("[project]\n", "name = \"foo\"\n"));
+        project_manifest.assert(concat!(
+            "[project]\n",
+            "name = \"foo\"\n",
+            "version = \"0.1.0\"\n"
+        ));
 
         Ok(())
     }
@@ -194,17 +261,107 @@ mod tests {
         let tmp_dir = assert_fs::TempDir::new()?;
         let project_root = tmp_dir.child("foo");
 
-        main(&project_root).with_context(|| "Failed to create new project!")?;
+        main(&project_root, None).with_context(|| "Failed to create new project!")?;
 
         let project_manifest = project_root.child("Cppargo.toml");
         let project_src = project_root.child("src");
         let project_hello_world = project_src.child("main.cpp");
 
         project_root.assert(predicates::path::is_dir());
-        project_manifest.assert("[project]\nname = \"foo\"\n");
+        project_manifest.assert("[project]\nname = \"foo\"\nversion = \"0.1.0\"\n");
         project_src.assert(predicates::path::is_dir());
         project_hello_world.assert(HELLO_WORLD_PROGRAM);
 
         Ok(())
     }
+
+    #[test]
+    fn proper_new_project_with_name() -> anyhow::Result<()> {
+        let tmp_dir = assert_fs::TempDir::new()?;
+        let project_root = tmp_dir.child("2048 game");
+
+        main(&project_root, Some("game")).with_context(|| "Failed to create new project!")?;
+
+        let project_manifest = project_root.child("Cppargo.toml");
+        project_manifest.assert("[project]\nname = \"game\"\nversion = \"0.1.0\"\n");
+
+        Ok(())
+    }
+
+    #[test]
+    fn fail_new_project_with_invalid_name() -> anyhow::Result<()> {
+        let tmp_dir = assert_fs::TempDir::new()?;
+        let project_root = tmp_dir.child("my app");
+
+        anyhow::ensure!(
+            main(&project_root, None).is_err(),
+            "Created project with invalid name!"
+        );
+        project_root.assert(predicates::path::missing());
+
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod validate_project_name {
+        use super::*;
+
+        #[test]
+        fn accept_valid_names() -> anyhow::Result<()> {
+            for name in ["foo", "foo_bar", "foo-bar", "_foo", "Foo2"] {
+                validate_project_name(name)?;
+            }
+
+            Ok(())
+        }
+
+        #[test]
+        fn reject_invalid_names() -> anyhow::Result<()> {
+            for name in ["", "2048", "my app", "foo$bar", "-foo", "föo"] {
+                anyhow::ensure!(
+                    validate_project_name(name).is_err(),
+                    format!("Accepted invalid project name `{name}`!")
+                );
+            }
+
+            Ok(())
+        }
+
+        #[test]
+        fn suggest_sanitized_name() -> anyhow::Result<()> {
+            let err = validate_project_name("my app").unwrap_err();
+
+            anyhow::ensure!(
+                err.to_string().contains("`--name my_app`"),
+                format!("Got unexpected suggestion: \"{err}\"!")
+            );
+
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod sanitize_project_name {
+        use super::*;
+
+        #[test]
+        fn sanitized_names_are_valid() -> anyhow::Result<()> {
+            for (name, expected) in [
+                ("foo", "foo"),
+                ("my app", "my_app"),
+                ("2048", "_2048"),
+                ("-foo", "_-foo"),
+                ("a.b", "a_b"),
+            ] {
+                let sanitized = sanitize_project_name(name);
+                anyhow::ensure!(
+                    sanitized == expected,
+                    format!("Sanitized `{name}` into `{sanitized}`, expected `{expected}`!")
+                );
+                validate_project_name(&sanitized)?;
+            }
+
+            Ok(())
+        }
+    }
 }
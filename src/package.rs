@@ -0,0 +1,297 @@
+use crate::{build, manifest, workspace, Context};
+use std::{
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{self, Command, Stdio},
+};
+
+/// Version control directories, which are never packaged.
+const VCS_DIRS: [&str; 4] = [".git", ".hg", ".svn", ".jj"];
+
+/// A source archive of a project.
+#[derive(Debug)]
+pub struct Package {
+    /// `NAME-VERSION`, the directory the files are archived inside of.
+    pub name: String,
+    pub archive: PathBuf,
+    /// Archived files, relative to the project root.
+    pub files: Vec<PathBuf>,
+}
+
+/// Archive the sources of the project containing `current_dir` into
+/// `target/package/NAME-VERSION.tar.gz`.
+///
+/// The manifest, every file inside `src/` and `include/`, the build script,
+/// the precompiled header and the manifest `[package] include` globs are
+/// archived, but never the `target/` directory or version control data. The
+/// archive only depends on the paths and contents of these files, so that
+/// packaging the same sources twice gives the same archive.
+pub fn create(current_dir: &Path) -> anyhow::Result<Package> {
+    let project_root = build::find_project_root(current_dir).with_context(|| {
+        format!(
+            "Current directory {} is not inside a `cppargo` project!",
+            current_dir.display()
+        )
+    })?;
+    let manifest = manifest::read(&project_root.join("Cppargo.toml"))?;
+    let Some(version) = &manifest.metadata.version else {
+        anyhow::bail!(format!(
+            "Project `{}` has no `version` in its `[project]` table to package it with!",
+            manifest.name
+        ));
+    };
+    let name = format!("{}-{version}", manifest.name);
+
+    let glob_set = build::build_glob_set(&package_globs(&manifest))?;
+    let files: Vec<_> = find_files(&project_root, Path::new(""))?
+        .into_iter()
+        .filter(|file| glob_set.is_match(file))
+        .collect();
+
+    // Workspace members are packaged into the `target/` directory they're
+    // built into.
+    let target_root =
+        workspace::find(&project_root)?.map_or(project_root.clone(), |workspace| workspace.root);
+    let package_dir = target_root.join("target").join("package");
+    fs::create_dir_all(&package_dir)
+        .with_context(|| format!("Failed to create directory {}!", package_dir.display()))?;
+    let archive = package_dir.join(format!("{name}.tar.gz"));
+    write_archive(&project_root, &files, &name, &archive)
+        .with_context(|| format!("Failed to write archive {}!", archive.display()))?;
+
+    Ok(Package {
+        name,
+        archive,
+        files,
+    })
+}
+
+/// Unpack the `package` into a temporary directory, and `build` the project
+/// unpacked there, to check that it builds from the archived files alone.
+pub fn verify(
+    package: &Package,
+    build: impl FnOnce(&Path) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let verify_dir = env::temp_dir().join(format!("cppargo-package-{}", process::id()));
+    let _ = fs::remove_dir_all(&verify_dir);
+    fs::create_dir_all(&verify_dir)
+        .with_context(|| format!("Failed to create directory {}!", verify_dir.display()))?;
+
+    let result =
+        unpack(&package.archive, &verify_dir).and_then(|()| build(&verify_dir.join(&package.name)));
+    let _ = fs::remove_dir_all(&verify_dir);
+
+    result
+}
+
+/// Globs, relative to the project root, of the files to package.
+fn package_globs(manifest: &manifest::Manifest) -> Vec<String> {
+    let mut globs: Vec<String> = ["Cppargo.toml", "build.cpp", "src/**", "include/**"]
+        .map(String::from)
+        .into();
+    if let Some(sources) = &manifest.build.sources {
+        globs.extend(sources.iter().cloned());
+    }
+    if let Some(manifest::BuildScript::Source(src_file)) = &manifest.build.script {
+        globs.push(src_file.to_string_lossy().into_owned());
+    }
+    if let Some(header) = &manifest.build.precompiled_header {
+        globs.push(header.to_string_lossy().into_owned());
+    }
+    globs.extend(manifest.package.include.iter().cloned());
+
+    globs
+}
+
+/// Every file inside `project_root/dir`, relative to `project_root`, except
+/// those inside the `target/` directory or version control directories.
+fn find_files(project_root: &Path, dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let read_dir = project_root.join(dir);
+    let mut files = Vec::new();
+    for entry in fs::read_dir(&read_dir)
+        .with_context(|| format!("Failed to read {}!", read_dir.display()))?
+    {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let is_excluded = (dir.as_os_str().is_empty() && file_name == "target")
+            || VCS_DIRS.iter().any(|vcs_dir| file_name == *vcs_dir);
+        if is_excluded {
+            continue;
+        }
+
+        let file = dir.join(file_name);
+        if entry.path().is_dir() {
+            files.extend(find_files(project_root, &file)?);
+        } else {
+            files.push(file);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+/// Archive the `files` inside the `name/` directory with `tar`, compressed
+/// with `gzip`, leaving out their modification times and owners.
+///
+/// The format and order of the archive are given explicitly, rather than
+/// left to the defaults of the local `tar`.
+fn write_archive(
+    project_root: &Path,
+    files: &[PathBuf],
+    name: &str,
+    archive: &Path,
+) -> anyhow::Result<()> {
+    let mut tar = Command::new("tar")
+        .args([
+            "--create",
+            "--format=gnu",
+            "--sort=name",
+            "--no-recursion",
+            "--mtime=@0",
+            "--owner=0",
+            "--group=0",
+            "--numeric-owner",
+            "--mode=go-w",
+        ])
+        .arg(format!("--transform=s,^,{name}/,S"))
+        .args(["--null", "--files-from=-"])
+        .current_dir(project_root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| "Couldn't start `tar`!")?;
+    let (Some(mut tar_stdin), Some(tar_stdout)) = (tar.stdin.take(), tar.stdout.take()) else {
+        anyhow::bail!("Failed to pipe the files to archive through `tar`!");
+    };
+    let output = fs::File::create(archive)?;
+    // `-n` leaves out the name and time of the archive from its header.
+    let mut gzip = Command::new("gzip")
+        .args(["-n", "-9"])
+        .stdin(tar_stdout)
+        .stdout(output)
+        .spawn()
+        .with_context(|| "Couldn't start `gzip`!")?;
+
+    for file in files {
+        tar_stdin.write_all(file.as_os_str().as_encoded_bytes())?;
+        tar_stdin.write_all(b"\0")?;
+    }
+    drop(tar_stdin);
+
+    let tar_status = tar.wait()?;
+    let gzip_status = gzip.wait()?;
+    anyhow::ensure!(
+        tar_status.success(),
+        format!("`tar` exited with {tar_status}!")
+    );
+    anyhow::ensure!(
+        gzip_status.success(),
+        format!("`gzip` exited with {gzip_status}!")
+    );
+
+    Ok(())
+}
+
+fn unpack(archive: &Path, dir: &Path) -> anyhow::Result<()> {
+    let status = Command::new("tar")
+        .arg("--extract")
+        .arg("--gzip")
+        .arg("--file")
+        .arg(archive)
+        .arg("--directory")
+        .arg(dir)
+        .status()
+        .with_context(|| "Couldn't start `tar`!")?;
+    anyhow::ensure!(
+        status.success(),
+        format!(
+            "Failed to unpack {}! `tar` exited with {status}.",
+            archive.display()
+        )
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+
+    #[test]
+    fn reproducible_package() -> anyhow::Result<()> {
+        let project_root = assert_fs::TempDir::new()?;
+        project_root.child("Cppargo.toml").write_str(concat!(
+            "[project]\n",
+            "name = \"foo\"\n",
+            "version = \"0.1.0\"\n",
+            "[package]\n",
+            "include = [\"README.md\"]\n"
+        ))?;
+        project_root
+            .child("src/main.cpp")
+            .write_str("int main() {}\n")?;
+        project_root
+            .child("include/foo.hpp")
+            .write_str("#pragma once\n")?;
+        project_root.child("README.md").write_str("# foo\n")?;
+        project_root.child("notes.txt").write_str("todo\n")?;
+        project_root.child("target/debug/foo").write_str("binary")?;
+        project_root.child(".git/HEAD").write_str("ref\n")?;
+
+        let package = create(&project_root)?;
+        let expected = [
+            "Cppargo.toml",
+            "README.md",
+            "include/foo.hpp",
+            "src/main.cpp",
+        ];
+        anyhow::ensure!(
+            package.name == "foo-0.1.0" && package.files == expected.map(PathBuf::from),
+            format!("Packaged unexpected files {package:?}!")
+        );
+        project_root
+            .child("target/package/foo-0.1.0.tar.gz")
+            .assert(predicates::path::is_file());
+
+        let archived = fs::read(&package.archive)?;
+        fs::File::options()
+            .write(true)
+            .open(project_root.child("src/main.cpp"))?
+            .set_modified(std::time::SystemTime::now())?;
+        let package = create(&project_root)?;
+        anyhow::ensure!(
+            fs::read(&package.archive)? == archived,
+            "Packaging the same files twice gave different archives!"
+        );
+
+        let mut unpacked = Vec::new();
+        verify(&package, |dir| {
+            unpacked = find_files(dir, Path::new(""))?;
+            Ok(())
+        })?;
+        anyhow::ensure!(
+            unpacked == expected.map(PathBuf::from),
+            format!("Unpacked unexpected files {unpacked:?}!")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn fail_without_version() -> anyhow::Result<()> {
+        let project_root = assert_fs::TempDir::new()?;
+        project_root
+            .child("Cppargo.toml")
+            .write_str("[project]\nname = \"foo\"\n")?;
+
+        anyhow::ensure!(
+            create(&project_root).is_err(),
+            "Packaged project without a version!"
+        );
+
+        Ok(())
+    }
+}
@@ -1,37 +1,55 @@
 use crate::anyhow::Context;
 
-use std::{
-    path::{Path, PathBuf},
-    process::Command,
-};
+use std::{collections::BTreeMap, path::Path, process::Command};
 
-pub fn main(project_root: &Path) -> anyhow::Result<()> {
-    let project_binary =
-        find_project_binary(project_root).with_context(|| "Failed to find project binary!")?;
-    run_project_binary(&project_binary).with_context(|| "Failed to run project binary!")?;
-
-    Ok(())
+/// How the binaries built by `build::main` are run.
+#[derive(Default)]
+pub struct Runner {
+    /// Target triple the binaries were built for, if they can't be run on
+    /// this host.
+    pub foreign_target: Option<String>,
+    /// Command the binaries are run through, such as an emulator or
+    /// `valgrind`, given the binary as its last argument.
+    pub command: Option<Vec<String>>,
+    /// Environment variables set for the binaries.
+    pub env: BTreeMap<String, String>,
 }
 
-fn find_project_binary(project_root: &Path) -> anyhow::Result<PathBuf> {
-    let project_target = project_root.join("target");
-
-    let Some(project_name) = project_root.file_name() else {
-        anyhow::bail!("Couldn't get project name.");
-    };
-
-    let project_binary = project_target.join(project_name);
-
+/// Run the `project_binary` built by `build::main` through `runner`.
+pub fn main(project_binary: &Path, runner: &Runner) -> anyhow::Result<()> {
     anyhow::ensure!(
-        &project_binary.exists(),
+        project_binary.exists(),
         format!("Project excecutable {} not found", project_binary.display())
     );
+    let command = command(project_binary, runner)?;
+    run_project_binary(command, project_binary).with_context(|| "Failed to run project binary!")?;
+
+    Ok(())
+}
+
+/// The command running `project_binary` through `runner`, with its
+/// environment variables set.
+pub fn command(project_binary: &Path, runner: &Runner) -> anyhow::Result<Command> {
+    let mut command = match runner.command.as_deref().and_then(<[String]>::split_first) {
+        Some((program, args)) => {
+            let mut command = Command::new(program);
+            command.args(args).arg(project_binary);
+            command
+        }
+        None => match &runner.foreign_target {
+            Some(target) => anyhow::bail!(format!(
+                "Can't run binaries built for `{target}` on this host! Set a `runner` in the manifest `[target.{target}]` table to run them through an emulator."
+            )),
+            None => Command::new(project_binary),
+        },
+    };
+    command.envs(&runner.env);
 
-    Ok(project_binary)
+    Ok(command)
 }
 
-fn run_project_binary(project_binary: &PathBuf) -> anyhow::Result<()> {
-    Command::new(project_binary)
+fn run_project_binary(mut command: Command, project_binary: &Path) -> anyhow::Result<()> {
+    command
         .spawn()
         .with_context(|| {
             format!(
@@ -51,16 +69,53 @@ mod tests {
     use assert_fs::prelude::*;
 
     #[test]
-    fn proper_find_project_binary() -> anyhow::Result<()> {
+    fn fail_missing_project_binary() -> anyhow::Result<()> {
         let project_root = assert_fs::TempDir::new()?;
-        let project_target = project_root.child("target");
-        let project_binary = project_target.child(project_root.file_name().unwrap());
-        project_binary.touch()?;
-        let found_project_binary = find_project_binary(&project_root)?;
+        let project_binary = project_root.child("target").child("foo");
+
+        anyhow::ensure!(
+            main(project_binary.path(), &Runner::default()).is_err(),
+            "Ran a missing project binary!"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn run_through_runner() -> anyhow::Result<()> {
+        let runner = Runner {
+            foreign_target: Some("aarch64-linux-gnu".to_string()),
+            command: Some(vec![
+                "qemu-aarch64".to_string(),
+                "-L".to_string(),
+                "/usr".to_string(),
+            ]),
+            env: BTreeMap::from([("LOG_LEVEL".to_string(), "debug".to_string())]),
+        };
+        let command = command(Path::new("target/foo"), &runner)?;
+
+        anyhow::ensure!(
+            command.get_program() == "qemu-aarch64"
+                && command.get_args().collect::<Vec<_>>() == ["-L", "/usr", "target/foo"]
+                && command
+                    .get_envs()
+                    .eq([("LOG_LEVEL".as_ref(), Some("debug".as_ref()))]),
+            format!("Got unexpected command {command:?}!")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn fail_foreign_target_without_runner() -> anyhow::Result<()> {
+        let runner = Runner {
+            foreign_target: Some("aarch64-linux-gnu".to_string()),
+            ..Default::default()
+        };
 
         anyhow::ensure!(
-            found_project_binary == project_binary.path(),
-            "Failed to find project binary!"
+            command(Path::new("target/foo"), &runner).is_err(),
+            "Ran a foreign binary without a runner!"
         );
 
         Ok(())
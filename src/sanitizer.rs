@@ -0,0 +1,163 @@
+use crate::Context;
+use std::{
+    collections::BTreeSet,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// A runtime sanitizer the project can be instrumented with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+pub enum Sanitizer {
+    /// Out-of-bounds accesses, use-after-free and other memory errors.
+    Address,
+    /// Undefined behavior, such as signed overflow or misaligned pointers.
+    Undefined,
+    /// Data races between threads.
+    Thread,
+    /// Memory leaks.
+    Leak,
+}
+
+/// Pairs of sanitizers whose runtimes can't be linked into the same binary.
+const INCOMPATIBLE_SANITIZERS: [(Sanitizer, Sanitizer); 2] = [
+    (Sanitizer::Address, Sanitizer::Thread),
+    (Sanitizer::Leak, Sanitizer::Thread),
+];
+
+impl Sanitizer {
+    /// Name of the sanitizer, as given to `-fsanitize=`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Address => "address",
+            Self::Undefined => "undefined",
+            Self::Thread => "thread",
+            Self::Leak => "leak",
+        }
+    }
+}
+
+/// Ensure `sanitizers` can be combined into the same binary.
+pub fn validate(sanitizers: &BTreeSet<Sanitizer>) -> anyhow::Result<()> {
+    for (a, b) in INCOMPATIBLE_SANITIZERS {
+        anyhow::ensure!(
+            !(sanitizers.contains(&a) && sanitizers.contains(&b)),
+            format!(
+                "The `{}` and `{}` sanitizers can't be combined!",
+                a.name(),
+                b.name()
+            )
+        );
+    }
+
+    Ok(())
+}
+
+/// Flags to both compile and link with in order to enable `sanitizers`.
+pub fn flags(sanitizers: &BTreeSet<Sanitizer>) -> Vec<String> {
+    if sanitizers.is_empty() {
+        return Vec::new();
+    }
+
+    vec![
+        format!("-fsanitize={}", list(sanitizers, ",")),
+        // Sanitizer reports are much more useful with full stack traces.
+        "-fno-omit-frame-pointer".to_string(),
+        "-g".to_string(),
+    ]
+}
+
+/// Name of the `target/` subdirectory for builds with `sanitizers`, so that
+/// they don't clobber normal builds.
+pub fn dir_name(sanitizers: &BTreeSet<Sanitizer>) -> String {
+    format!("sanitize-{}", list(sanitizers, "-"))
+}
+
+/// Ensure `compiler` can build and link a program with `sanitizers`, since
+/// their runtimes are often packaged separately from the compiler, and not
+/// every compiler supports every sanitizer.
+pub fn ensure_supported(compiler: &str, sanitizers: &BTreeSet<Sanitizer>) -> anyhow::Result<()> {
+    let mut probe = Command::new(compiler);
+    probe
+        .args(flags(sanitizers))
+        .args(["-x", "c++", "-", "-o", "/dev/null"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+
+    let mut child = probe
+        .spawn()
+        .with_context(|| format!("Couldn't start compiler: {probe:?}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(b"int main() { return 0; }\n")?;
+    }
+    let output = child.wait_with_output()?;
+
+    anyhow::ensure!(
+        output.status.success(),
+        format!(
+            "Compiler `{compiler}` doesn't support the `{}` sanitizer{}! Is its runtime installed?\n{}",
+            list(sanitizers, ","),
+            if sanitizers.len() == 1 { "" } else { "s" },
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        )
+    );
+
+    Ok(())
+}
+
+fn list(sanitizers: &BTreeSet<Sanitizer>, separator: &str) -> String {
+    sanitizers
+        .iter()
+        .map(|sanitizer| sanitizer.name())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reject_incompatible_sanitizers() -> anyhow::Result<()> {
+        for (sanitizers, valid) in [
+            (vec![Sanitizer::Address, Sanitizer::Undefined], true),
+            (vec![Sanitizer::Address, Sanitizer::Leak], true),
+            (vec![Sanitizer::Thread, Sanitizer::Undefined], true),
+            (vec![Sanitizer::Address, Sanitizer::Thread], false),
+            (vec![Sanitizer::Thread, Sanitizer::Leak], false),
+        ] {
+            let sanitizers = BTreeSet::from_iter(sanitizers);
+            anyhow::ensure!(
+                validate(&sanitizers).is_ok() == valid,
+                format!("Expected {sanitizers:?} to be valid: {valid}!")
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn proper_flags() -> anyhow::Result<()> {
+        let sanitizers = BTreeSet::from([Sanitizer::Undefined, Sanitizer::Address]);
+
+        anyhow::ensure!(
+            flags(&sanitizers)
+                == [
+                    "-fsanitize=address,undefined",
+                    "-fno-omit-frame-pointer",
+                    "-g"
+                ],
+            format!("Got unexpected flags {:?}!", flags(&sanitizers))
+        );
+        anyhow::ensure!(
+            dir_name(&sanitizers) == "sanitize-address-undefined",
+            format!("Got unexpected directory {}!", dir_name(&sanitizers))
+        );
+        anyhow::ensure!(
+            flags(&BTreeSet::new()).is_empty(),
+            "Got flags without sanitizers!"
+        );
+
+        Ok(())
+    }
+}
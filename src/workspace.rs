@@ -0,0 +1,264 @@
+use crate::{build, manifest, Context};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// A workspace of member projects, built into a shared `target/` directory at
+/// its root.
+#[derive(Debug)]
+pub struct Workspace {
+    pub root: PathBuf,
+    /// Root directories of the member projects, by project name.
+    pub members: BTreeMap<String, PathBuf>,
+}
+
+impl Workspace {
+    /// Root directories of the projects to build: the `package` member if
+    /// given, every member if `project_root` is the workspace root, and
+    /// otherwise only the member at `project_root`.
+    pub fn select(
+        &self,
+        project_root: &Path,
+        package: Option<&str>,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        match package {
+            Some(package) => match self.members.get(package) {
+                Some(member) => Ok(vec![member.clone()]),
+                None => anyhow::bail!(format!(
+                    "Workspace {} has no member `{package}`! Its members are: {}.",
+                    self.root.display(),
+                    self.members
+                        .keys()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )),
+            },
+            None if project_root == self.root => {
+                anyhow::ensure!(
+                    !self.members.is_empty(),
+                    format!("Workspace {} has no members!", self.root.display())
+                );
+                Ok(self.members.values().cloned().collect())
+            }
+            None => Ok(vec![project_root.to_path_buf()]),
+        }
+    }
+}
+
+/// The workspace containing the project at `project_root`, which is either
+/// its root or one of its members, if any.
+pub fn find(project_root: &Path) -> anyhow::Result<Option<Workspace>> {
+    for dir in project_root.ancestors() {
+        let manifest_path = dir.join("Cppargo.toml");
+        if !manifest_path.is_file() {
+            continue;
+        }
+        let Some(config) = manifest::read_workspace(&manifest_path)? else {
+            continue;
+        };
+
+        let workspace = read(dir, &config)
+            .with_context(|| format!("Failed to read workspace {}!", dir.display()))?;
+        let is_member = dir == project_root
+            || workspace
+                .members
+                .values()
+                .any(|member| member == project_root);
+
+        return Ok(is_member.then_some(workspace));
+    }
+
+    Ok(None)
+}
+
+/// Read the workspace at `root`, looking up the member projects matching the
+/// globs of its `config`.
+fn read(root: &Path, config: &manifest::WorkspaceConfig) -> anyhow::Result<Workspace> {
+    let mut member_dirs = find_member_dirs(root, &config.members)?;
+    if config.root_project {
+        member_dirs.insert(root.to_path_buf());
+    }
+
+    let mut members: BTreeMap<String, PathBuf> = BTreeMap::new();
+    for member_dir in member_dirs {
+        let name = manifest::read(&member_dir.join("Cppargo.toml"))
+            .with_context(|| {
+                format!(
+                    "Failed to read manifest of workspace member {}!",
+                    member_dir.display()
+                )
+            })?
+            .name;
+        if let Some(other_dir) = members.get(&name) {
+            anyhow::bail!(format!(
+                "Workspace members {} and {} are both named `{name}`!",
+                other_dir.display(),
+                member_dir.display()
+            ));
+        }
+        members.insert(name, member_dir);
+    }
+
+    Ok(Workspace {
+        root: root.to_path_buf(),
+        members,
+    })
+}
+
+/// Directories inside `root` with a `Cppargo.toml` matching the `globs`.
+fn find_member_dirs(root: &Path, globs: &[String]) -> anyhow::Result<BTreeSet<PathBuf>> {
+    let glob_set = build::build_glob_set(globs)?;
+
+    let mut member_dirs = BTreeSet::new();
+    for glob in globs {
+        let base = root.join(build::glob_base(glob));
+        if base.is_dir() {
+            visit_dir(root, &base, &glob_set, &mut member_dirs)?;
+        }
+
+        // Unlike a glob, a plain path names a member which has to exist.
+        let is_path = build::glob_base(glob) == Path::new(glob);
+        anyhow::ensure!(
+            !is_path || member_dirs.contains(&base),
+            format!("Workspace member `{glob}` has no manifest `Cppargo.toml`!")
+        );
+    }
+
+    Ok(member_dirs)
+}
+
+fn visit_dir(
+    root: &Path,
+    dir: &Path,
+    glob_set: &globset::GlobSet,
+    member_dirs: &mut BTreeSet<PathBuf>,
+) -> anyhow::Result<()> {
+    let relative_dir = dir.strip_prefix(root).unwrap_or(dir);
+    if glob_set.is_match(relative_dir) && dir.join("Cppargo.toml").is_file() {
+        member_dirs.insert(dir.to_path_buf());
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {}!", dir.display()))? {
+        let path = entry?.path();
+        let skipped = path
+            .file_name()
+            .is_some_and(|name| name == "target" || name.to_string_lossy().starts_with('.'));
+        if path.is_dir() && !skipped {
+            visit_dir(root, &path, glob_set, member_dirs)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+
+    fn workspace_dir() -> anyhow::Result<assert_fs::TempDir> {
+        let workspace_root = assert_fs::TempDir::new()?;
+        workspace_root
+            .child("Cppargo.toml")
+            .write_str("[workspace]\nmembers = [\"libs/*\", \"apps/cli\"]\n")?;
+        for (dir, name) in [
+            ("libs/core", "core"),
+            ("libs/net", "net"),
+            ("apps/cli", "cli"),
+        ] {
+            workspace_root
+                .child(dir)
+                .child("Cppargo.toml")
+                .write_str(&format!("[project]\nname = \"{name}\"\n"))?;
+        }
+        // Not a member, since it has no manifest.
+        workspace_root.child("libs/docs").create_dir_all()?;
+
+        Ok(workspace_root)
+    }
+
+    #[test]
+    fn find_from_member() -> anyhow::Result<()> {
+        let workspace_root = workspace_dir()?;
+        let member = workspace_root.child("libs/net");
+
+        let Some(workspace) = find(member.path())? else {
+            anyhow::bail!("Failed to find workspace of {}!", member.display());
+        };
+        anyhow::ensure!(
+            workspace.root == workspace_root.path()
+                && workspace.members
+                    == BTreeMap::from([
+                        ("cli".to_string(), workspace_root.join("apps/cli")),
+                        ("core".to_string(), workspace_root.join("libs/core")),
+                        ("net".to_string(), workspace_root.join("libs/net")),
+                    ]),
+            format!("Got unexpected workspace {workspace:?}!")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn ignore_non_member() -> anyhow::Result<()> {
+        let workspace_root = workspace_dir()?;
+        let project = workspace_root.child("tools/gen");
+        project
+            .child("Cppargo.toml")
+            .write_str("[project]\nname = \"gen\"\n")?;
+
+        anyhow::ensure!(
+            find(project.path())?.is_none(),
+            "Found workspace of a project which isn't a member!"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn select_members() -> anyhow::Result<()> {
+        let workspace_root = workspace_dir()?;
+        let Some(workspace) = find(workspace_root.path())? else {
+            anyhow::bail!("Failed to find workspace at {}!", workspace_root.display());
+        };
+        let member = workspace_root.join("libs/core");
+
+        anyhow::ensure!(
+            workspace.select(workspace_root.path(), None)?.len() == 3,
+            "Didn't select every member at the workspace root!"
+        );
+        anyhow::ensure!(
+            workspace.select(&member, None)? == [member.clone()],
+            "Didn't select only the current member!"
+        );
+        anyhow::ensure!(
+            workspace.select(&member, Some("cli"))? == [workspace_root.join("apps/cli")],
+            "Didn't select the named member!"
+        );
+        anyhow::ensure!(
+            workspace.select(&member, Some("gui")).is_err(),
+            "Selected a missing member!"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn fail_missing_member_path() -> anyhow::Result<()> {
+        let workspace_root = workspace_dir()?;
+        workspace_root
+            .child("Cppargo.toml")
+            .write_str("[workspace]\nmembers = [\"apps/gui\"]\n")?;
+
+        anyhow::ensure!(
+            find(workspace_root.path()).is_err(),
+            "Accepted a workspace with a missing member!"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+mod common;
+use common::*;
+
+#[test]
+fn succeed_comparing_against_baseline() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+    let project_src = project_root.child("src");
+    project_src.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(PROJECT_MANIFEST)?;
+
+    let main_file = project_src.child("main.cpp");
+    main_file.write_str(HELLO_WORLD_PROGRAM)?;
+    let lib_file = project_src.child("fib.cpp");
+    lib_file.write_str(concat!(
+        "unsigned fib(unsigned n) {\n",
+        "    return n < 2 ? n : fib(n - 1) + fib(n - 2);\n",
+        "}\n"
+    ))?;
+
+    let bench_file = project_root.child("benches/fib.cpp");
+    bench_file.write_str(concat!(
+        "unsigned fib(unsigned n);\n",
+        "\n",
+        "int main() {\n",
+        "    return fib(20) == 6765 ? 0 : 1;\n",
+        "}\n"
+    ))?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("bench");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("bench fib ... "))
+        .stdout(predicate::str::contains("vs baseline").not());
+
+    project_root
+        .child("target/release/benches/fib")
+        .assert(predicate::path::is_file());
+    project_root
+        .child("target/bench/baseline.json")
+        .assert(predicate::str::contains("\"fib\""));
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("bench");
+    cmd.assert().success().stdout(predicate::str::is_match(
+        r"bench fib \.\.\. .* \(median of 5 runs, [+-]\d+\.\d{2}% vs baseline\)",
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn fail_because_benchmark_failed() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+    let project_src = project_root.child("src");
+    project_src.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(PROJECT_MANIFEST)?;
+
+    let main_file = project_src.child("main.cpp");
+    main_file.write_str(HELLO_WORLD_PROGRAM)?;
+
+    let bench_file = project_root.child("benches/broken.cpp");
+    bench_file.write_str("int main() {\n    return 1;\n}\n")?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("bench");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Failed to run benchmark `broken`!",
+    ));
+
+    Ok(())
+}
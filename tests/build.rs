@@ -5,7 +5,7 @@ use common::*;
 fn fail_outside_cppargo_project() -> anyhow::Result<()> {
     let tmp_dir = assert_fs::TempDir::new()?;
 
-    let mut cmd = Command::cargo_bin("cppargo")?;
+    let mut cmd = cppargo()?;
     cmd.current_dir(tmp_dir.path()).arg("build");
     cmd.assert().failure().stderr(predicate::str::contains(
         "Failed to find project manifest `Cppargo.toml` up to /!",
@@ -26,7 +26,7 @@ fn fail_because_project_has_no_main_file() -> anyhow::Result<()> {
     let project_manifest = project_root.child("Cppargo.toml");
     project_manifest.write_str(PROJECT_MANIFEST)?;
 
-    let mut cmd = Command::cargo_bin("cppargo")?;
+    let mut cmd = cppargo()?;
     cmd.current_dir(project_root.path()).arg("build");
     cmd.assert().failure().stderr(predicate::str::contains(
         format!(
@@ -52,7 +52,7 @@ fn succeed_without_existing_target_dir() -> anyhow::Result<()> {
     let main_file = project_src.child("main.cpp");
     main_file.write_str(HELLO_WORLD_PROGRAM)?;
 
-    let mut cmd = Command::cargo_bin("cppargo")?;
+    let mut cmd = cppargo()?;
     cmd.current_dir(project_root.path()).arg("build");
     cmd.assert()
         .success()
@@ -80,7 +80,7 @@ fn succeed_with_existing_target_dir() -> anyhow::Result<()> {
     let existing_binary = project_target.child("foo");
     existing_binary.touch()?;
 
-    let mut cmd = Command::cargo_bin("cppargo")?;
+    let mut cmd = cppargo()?;
     cmd.current_dir(project_root.path()).arg("build");
     cmd.assert()
         .success()
@@ -88,3 +88,1084 @@ fn succeed_with_existing_target_dir() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn succeed_with_c_and_cpp_sources() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+    let project_src = project_root.child("src");
+    project_src.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(concat!(
+        "[project]\nname = \"foo\"\n",
+        "[build]\nc-standard = \"c99\"\n"
+    ))?;
+
+    let main_file = project_src.child("main.cpp");
+    main_file.write_str(concat!(
+        "#include <iostream>\n",
+        "\n",
+        "extern \"C\" const char *greeting();\n",
+        "\n",
+        "int main() {\n",
+        "    std::cout << greeting() << \"\\n\";\n",
+        "\n",
+        "    return 0;\n",
+        "}\n"
+    ))?;
+    let c_file = project_src.child("greeting.c");
+    c_file.write_str(concat!(
+        "const char *greeting(void) {\n",
+        "    return \"Hello C!\";\n",
+        "}\n"
+    ))?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("build");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("-std=c99"))
+        .stdout(predicate::str::contains("Project built successfully!"));
+
+    project_root
+        .child("target/foo")
+        .assert(predicate::path::is_file());
+
+    Ok(())
+}
+
+#[test]
+fn succeed_excluding_broken_sources() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+    let project_src = project_root.child("src");
+    project_src.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(concat!(
+        "[project]\nname = \"foo\"\n",
+        "[build]\nsources = [\"src/**\"]\nexclude = [\"src/experimental/**\"]\n",
+        "[target.'cfg(windows)'.build]\nsources = [\"src/experimental/*.cpp\"]\n"
+    ))?;
+
+    let main_file = project_src.child("main.cpp");
+    main_file.write_str(HELLO_WORLD_PROGRAM)?;
+    let prototype_file = project_src.child("experimental/prototype.cpp");
+    prototype_file.write_str("this is not C++\n")?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("build");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("prototype.cpp").not())
+        .stdout(predicate::str::contains("Project built successfully!"));
+
+    Ok(())
+}
+
+#[test]
+fn warn_about_broken_symlinks() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+    let project_src = project_root.child("src");
+    project_src.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(PROJECT_MANIFEST)?;
+
+    let main_file = project_src.child("main.cpp");
+    main_file.write_str(HELLO_WORLD_PROGRAM)?;
+    let broken_link = project_src.child("broken.cpp");
+    broken_link.symlink_to_file(tmp_dir.child("missing.cpp").path())?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("build");
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "[WARN] Skipping unreadable source entry",
+        ))
+        .stdout(predicate::str::contains("Project built successfully!"));
+
+    Ok(())
+}
+
+#[test]
+fn fail_because_of_broken_symlink_with_strict_sources() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+    let project_src = project_root.child("src");
+    project_src.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(concat!(
+        "[project]\nname = \"foo\"\n",
+        "[build]\nstrict-sources = true\n"
+    ))?;
+
+    let main_file = project_src.child("main.cpp");
+    main_file.write_str(HELLO_WORLD_PROGRAM)?;
+    let broken_link = project_src.child("broken.cpp");
+    broken_link.symlink_to_file(tmp_dir.child("missing.cpp").path())?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("build");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "Broken symlink {}",
+            broken_link.path().display()
+        )));
+
+    Ok(())
+}
+
+#[test]
+fn succeed_writing_compile_commands() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+    let project_src = project_root.child("src");
+    project_src.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(PROJECT_MANIFEST)?;
+
+    let main_file = project_src.child("main.cpp");
+    main_file.write_str(HELLO_WORLD_PROGRAM)?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .args(["build", "--compile-commands"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Project built successfully!"));
+
+    project_root
+        .child("compile_commands.json")
+        .assert(predicate::str::contains("\"file\": \"src/main.cpp\""))
+        .assert(predicate::str::contains(format!(
+            "\"directory\": \"{}\"",
+            project_root.path().display()
+        )));
+
+    Ok(())
+}
+
+fn json_events(stdout: &[u8]) -> anyhow::Result<Vec<serde_json::Value>> {
+    std::str::from_utf8(stdout)?
+        .lines()
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+#[test]
+fn succeed_with_json_messages() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+    let project_src = project_root.child("src");
+    project_src.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(PROJECT_MANIFEST)?;
+
+    let main_file = project_src.child("main.cpp");
+    main_file.write_str(HELLO_WORLD_PROGRAM)?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .args(["build", "--message-format", "json"]);
+    let output = cmd.assert().success().get_output().clone();
+
+    let events = json_events(&output.stdout)?;
+    let reasons: Vec<&str> = events
+        .iter()
+        .map(|event| event["reason"].as_str().unwrap_or_default())
+        .collect();
+    anyhow::ensure!(
+        reasons
+            == [
+                "build-started",
+                "compiler-invocation",
+                "compiler-invocation",
+                "artifact",
+                "build-finished"
+            ],
+        format!("Got unexpected events: {reasons:?}")
+    );
+    anyhow::ensure!(
+        events[1]["file"] == "src/main.cpp",
+        format!("Got unexpected compiler invocation: {}", events[1])
+    );
+    anyhow::ensure!(
+        events[3]["path"] == project_root.child("target/foo").to_string_lossy().as_ref(),
+        format!("Got unexpected artifact: {}", events[3])
+    );
+    anyhow::ensure!(
+        events[4]["success"] == true,
+        format!("Got unexpected build result: {}", events[4])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn fail_with_json_messages() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+    let project_src = project_root.child("src");
+    project_src.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(PROJECT_MANIFEST)?;
+
+    let main_file = project_src.child("main.cpp");
+    main_file.write_str("int main() { return undeclared; }\n")?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .args(["build", "--message-format", "json"]);
+    let output = cmd.assert().failure().get_output().clone();
+
+    let events = json_events(&output.stdout)?;
+    anyhow::ensure!(
+        events.iter().any(|event| event["reason"] == "diagnostic"
+            && event["rendered"]
+                .as_str()
+                .is_some_and(|rendered| rendered.contains("undeclared"))),
+        format!("Missing diagnostic event: {events:?}")
+    );
+    anyhow::ensure!(
+        events
+            .last()
+            .is_some_and(|event| event["reason"] == "build-finished" && event["success"] == false),
+        format!("Missing failed build-finished event: {events:?}")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn succeed_checking_without_linking() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+    let project_src = project_root.child("src");
+    project_src.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(PROJECT_MANIFEST)?;
+
+    let main_file = project_src.child("main.cpp");
+    main_file.write_str(HELLO_WORLD_PROGRAM)?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .args(["check", "--message-format", "json"]);
+    let output = cmd.assert().success().get_output().clone();
+
+    let events = json_events(&output.stdout)?;
+    let reasons: Vec<&str> = events
+        .iter()
+        .map(|event| event["reason"].as_str().unwrap_or_default())
+        .collect();
+    anyhow::ensure!(
+        reasons == ["build-started", "compiler-invocation", "build-finished"],
+        format!("Got unexpected events: {reasons:?}")
+    );
+    project_root
+        .child("target/obj/src/main.cpp.o")
+        .assert(predicate::path::is_file());
+    project_root
+        .child("target/foo")
+        .assert(predicate::path::missing());
+
+    // Libraries, without `src/main.cpp`, are checked on their own.
+    std::fs::remove_file(main_file.path())?;
+    project_src
+        .child("foo.cpp")
+        .write_str("int foo() { return 0; }\n")?;
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("check");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Checking project..."))
+        .stdout(predicate::str::contains("Project checked successfully!"))
+        .stdout(predicate::str::contains("built").not());
+    project_root
+        .child("target/obj/src/foo.cpp.o")
+        .assert(predicate::path::is_file());
+
+    project_src
+        .child("foo.cpp")
+        .write_str("int foo() { return undeclared; }\n")?;
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("check");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to check project."));
+
+    Ok(())
+}
+
+#[test]
+fn fail_with_diagnostics_summary_and_sarif() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+    let project_src = project_root.child("src");
+    project_src.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(PROJECT_MANIFEST)?;
+
+    let main_file = project_src.child("main.cpp");
+    main_file.write_str(concat!(
+        "int no_return() {}\n",
+        "\n",
+        "int main() {\n",
+        "    return undeclared;\n",
+        "}\n"
+    ))?;
+
+    let sarif = tmp_dir.child("diagnostics.sarif");
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .arg("build")
+        .arg("--sarif")
+        .arg(sarif.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("  --> src/main.cpp:4:12\n"))
+        .stderr(predicate::str::contains(
+            "Build finished with 1 warning and 1 error.",
+        ));
+
+    let log: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(sarif.path())?)?;
+    let levels: Vec<&str> = log["runs"][0]["results"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|result| result["level"].as_str().unwrap_or_default())
+        .collect();
+    anyhow::ensure!(
+        levels == ["warning", "error"],
+        format!("Got unexpected SARIF results: {log:#}")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn fail_because_of_denied_warnings() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+    let project_src = project_root.child("src");
+    project_src.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(concat!(
+        "[project]\n",
+        "name = \"foo\"\n",
+        "\n",
+        "[lints]\n",
+        "level = \"extra\"\n"
+    ))?;
+
+    let main_file = project_src.child("main.cpp");
+    main_file.write_str("int main(int argc, char **argv) {\n    return 0;\n}\n")?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("build");
+    cmd.assert().success().stderr(predicate::str::contains(
+        "Build finished with 2 warnings and 0 errors.",
+    ));
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .arg("build")
+        .arg("--deny-warnings");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Build finished with 0 warnings and 2 errors.",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn succeed_with_sanitizers() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+    let project_src = project_root.child("src");
+    project_src.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(PROJECT_MANIFEST)?;
+
+    let main_file = project_src.child("main.cpp");
+    main_file.write_str(concat!(
+        "int main() {\n",
+        "    int *numbers = new int[4];\n",
+        "    int number = numbers[4];\n",
+        "    delete[] numbers;\n",
+        "    return number;\n",
+        "}\n"
+    ))?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .arg("build")
+        .arg("--sanitize")
+        .arg("address,undefined");
+    cmd.assert().success();
+
+    project_root
+        .child("target/sanitize-address-undefined/foo")
+        .assert(predicate::path::is_file());
+    project_root
+        .child("target/foo")
+        .assert(predicate::path::missing());
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .arg("run")
+        .arg("--sanitize")
+        .arg("address");
+    cmd.assert().stderr(predicate::str::contains(
+        "AddressSanitizer: heap-buffer-overflow",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn fail_because_of_incompatible_sanitizers() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+    let project_src = project_root.child("src");
+    project_src.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(PROJECT_MANIFEST)?;
+
+    let main_file = project_src.child("main.cpp");
+    main_file.write_str(HELLO_WORLD_PROGRAM)?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .arg("build")
+        .arg("--sanitize")
+        .arg("address,thread");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "The `address` and `thread` sanitizers can't be combined!",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn succeed_with_release_profile() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+    let project_src = project_root.child("src");
+    project_src.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(concat!(
+        "[project]\n",
+        "name = \"foo\"\n",
+        "\n",
+        "[profile.release]\n",
+        "opt-level = 2\n",
+        "debug = true\n"
+    ))?;
+
+    let main_file = project_src.child("main.cpp");
+    main_file.write_str(HELLO_WORLD_PROGRAM)?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .arg("build")
+        .arg("--release");
+    cmd.assert().success().stdout(predicate::str::contains(
+        r#""-O2" "-g" "-DNDEBUG" "-fdiagnostics-parseable-fixits""#,
+    ));
+
+    project_root
+        .child("target/release/foo")
+        .assert(predicate::path::is_file());
+    project_root
+        .child("target/foo")
+        .assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn succeed_with_lto_and_linker() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(concat!(
+        "[project]\n",
+        "name = \"foo\"\n",
+        "\n",
+        "[profile.release]\n",
+        "lto = \"full\"\n",
+        "linker = \"bfd\"\n"
+    ))?;
+    project_root
+        .child("src/main.cpp")
+        .write_str(HELLO_WORLD_PROGRAM)?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .arg("run")
+        .arg("--release");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("-flto"))
+        .stdout(predicate::str::contains("-fuse-ld=bfd"))
+        .stdout(predicate::str::contains("Hello World!"));
+
+    Ok(())
+}
+
+#[test]
+fn succeed_stripping_with_split_debuginfo() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(concat!(
+        "[project]\n",
+        "name = \"foo\"\n",
+        "\n",
+        "[profile.release]\n",
+        "debug = true\n",
+        "strip = \"symbols\"\n",
+        "split-debuginfo = true\n"
+    ))?;
+    project_root
+        .child("src/main.cpp")
+        .write_str(HELLO_WORLD_PROGRAM)?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .arg("run")
+        .arg("--release");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Hello World!"));
+
+    let sections = |file: &str| -> anyhow::Result<String> {
+        let output = std::process::Command::new("readelf")
+            .arg("--section-headers")
+            .arg(project_root.child(file).path())
+            .output()?;
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+    let binary_sections = sections("target/release/foo")?;
+    anyhow::ensure!(
+        binary_sections.contains(".gnu_debuglink")
+            && !binary_sections.contains(".debug_info")
+            && !binary_sections.contains(".symtab"),
+        format!("Binary wasn't stripped:\n{binary_sections}")
+    );
+    anyhow::ensure!(
+        sections("target/release/foo.debug")?.contains(".debug_info"),
+        "Debug information wasn't split off!"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn succeed_building_workspace_members() -> anyhow::Result<()> {
+    let workspace_root = assert_fs::TempDir::new()?;
+    workspace_root
+        .child("Cppargo.toml")
+        .write_str("[workspace]\nmembers = [\"libs/*\", \"apps/*\"]\n")?;
+    // Members without `src/main.cpp`, such as `core`, are libraries.
+    for (dir, name, src_file, source) in [
+        (
+            "libs/core",
+            "core",
+            "src/core.cpp",
+            "int answer() { return 42; }\n",
+        ),
+        ("apps/cli", "cli", "src/main.cpp", HELLO_WORLD_PROGRAM),
+    ] {
+        let member = workspace_root.child(dir);
+        member
+            .child("Cppargo.toml")
+            .write_str(&format!("[project]\nname = \"{name}\"\n"))?;
+        member.child(src_file).write_str(source)?;
+    }
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(workspace_root.path()).arg("build");
+    cmd.assert().success();
+    for (name, src_file) in [("core", "src/core.cpp"), ("cli", "src/main.cpp")] {
+        workspace_root
+            .child("target/obj")
+            .child(name)
+            .child(format!("{src_file}.o"))
+            .assert(predicate::path::is_file());
+    }
+    workspace_root
+        .child("target/cli")
+        .assert(predicate::path::is_file());
+    workspace_root
+        .child("target/core")
+        .assert(predicate::path::missing());
+    workspace_root
+        .child("apps/cli/target")
+        .assert(predicate::path::missing());
+
+    std::fs::remove_dir_all(workspace_root.child("target"))?;
+    let mut cmd = cppargo()?;
+    cmd.current_dir(workspace_root.child("apps/cli").path())
+        .arg("build")
+        .arg("-p")
+        .arg("core");
+    cmd.assert().success();
+    workspace_root
+        .child("target/obj/core/src/core.cpp.o")
+        .assert(predicate::path::is_file());
+    workspace_root
+        .child("target/cli")
+        .assert(predicate::path::missing());
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(workspace_root.child("apps/cli/src").path())
+        .arg("build");
+    cmd.assert().success();
+    workspace_root
+        .child("target/cli")
+        .assert(predicate::path::is_file());
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(workspace_root.path()).arg("run");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Several workspace members were built! Select one of them with `-p NAME`.",
+    ));
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(workspace_root.path())
+        .arg("run")
+        .arg("-p")
+        .arg("cli");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Hello World!"));
+
+    Ok(())
+}
+
+#[test]
+fn succeed_with_build_script() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(PROJECT_MANIFEST)?;
+    project_root.child("version.txt").write_str("1.2.3")?;
+
+    let build_script = project_root.child("build.cpp");
+    build_script.write_str(concat!(
+        "#include <cstdlib>\n",
+        "#include <fstream>\n",
+        "#include <iostream>\n",
+        "#include <string>\n",
+        "\n",
+        "int main() {\n",
+        "    std::string out_dir = std::getenv(\"CPPARGO_OUT_DIR\");\n",
+        "    std::string version;\n",
+        "    std::ifstream(\"version.txt\") >> version;\n",
+        "    std::ofstream(out_dir + \"/version.hpp\")\n",
+        "        << \"const char *version();\\n\";\n",
+        "    std::ofstream(out_dir + \"/version.cpp\")\n",
+        "        << \"const char *version() { return \\\"\" << version << \"\\\"; }\\n\";\n",
+        "    std::cout << \"cppargo:include=\" << out_dir << \"\\n\";\n",
+        "    std::cout << \"cppargo:source=\" << out_dir << \"/version.cpp\\n\";\n",
+        "    std::cout << \"cppargo:define=GREETING=\\\"Hello\\\"\\n\";\n",
+        "    std::cout << \"cppargo:rerun-if-changed=version.txt\\n\";\n",
+        "    return 0;\n",
+        "}\n"
+    ))?;
+
+    let main_file = project_root.child("src/main.cpp");
+    main_file.write_str(concat!(
+        "#include <iostream>\n",
+        "#include \"version.hpp\"\n",
+        "\n",
+        "int main() {\n",
+        "    std::cout << GREETING << \" from \" << version() << std::endl;\n",
+        "    return 0;\n",
+        "}\n"
+    ))?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("run");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("build.cpp"))
+        .stdout(predicate::str::contains("Hello from 1.2.3"));
+
+    // The script only runs again once `version.txt` changes.
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("run");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("build.cpp").not())
+        .stdout(predicate::str::contains("Hello from 1.2.3"));
+
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    project_root.child("version.txt").write_str("1.2.4")?;
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("run");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Hello from 1.2.4"));
+
+    Ok(())
+}
+
+#[test]
+fn fail_because_of_build_script() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(concat!(
+        "[project]\n",
+        "name = \"foo\"\n",
+        "\n",
+        "[build]\n",
+        "script = [\"sh\", \"-c\", \"echo cppargo:warning=generating; exit 3\"]\n"
+    ))?;
+    project_root
+        .child("src/main.cpp")
+        .write_str(HELLO_WORLD_PROGRAM)?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("build");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to run build script!"))
+        .stderr(predicate::str::contains(
+            "Build script exited with exit status: 3!",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn succeed_with_precompiled_header() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(concat!(
+        "[project]\n",
+        "name = \"foo\"\n",
+        "\n",
+        "[build]\n",
+        "precompiled-header = \"src/pch.hpp\"\n"
+    ))?;
+    let header = project_root.child("src/pch.hpp");
+    header.write_str(concat!(
+        "#pragma once\n",
+        "#include <iostream>\n",
+        "#include \"greeting.hpp\"\n"
+    ))?;
+    let greeting = project_root.child("src/greeting.hpp");
+    greeting.write_str("#define GREETING \"Hello\"\n")?;
+    project_root.child("src/main.cpp").write_str(concat!(
+        "int main() {\n",
+        "    std::cout << GREETING << std::endl;\n",
+        "    return 0;\n",
+        "}\n"
+    ))?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("run");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("c++-header"))
+        .stdout(predicate::str::contains("Hello"));
+    project_root
+        .child("target/pch/pch.hpp.gch")
+        .assert(predicate::path::is_file());
+    // The header is included the same way wherever the project is.
+    let wrapper = project_root.child("target/pch/pch.hpp");
+    wrapper.assert("#include \"../../src/pch.hpp\"\n");
+    let written = std::fs::metadata(wrapper.path())?.modified()?;
+
+    // The header is only compiled again once it, or a header it includes,
+    // changes.
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("build");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("c++-header").not());
+    anyhow::ensure!(
+        std::fs::metadata(wrapper.path())?.modified()? == written,
+        "Wrote the unchanged precompiled header wrapper again!"
+    );
+
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    greeting.write_str("#define GREETING \"Hi\"\n")?;
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("run");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("c++-header"))
+        .stdout(predicate::str::contains("Hi"));
+
+    Ok(())
+}
+
+#[test]
+fn succeed_with_modules() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(PROJECT_MANIFEST)?;
+    project_root
+        .child("src/math.cppm")
+        .write_str("export module math;\nexport import :ops;\n")?;
+    project_root.child("src/ops.cppm").write_str(concat!(
+        "export module math:ops;\n",
+        "export int add(int a, int b) { return a + b; }\n"
+    ))?;
+    project_root.child("src/main.cpp").write_str(concat!(
+        "#include <iostream>\n",
+        "import math;\n",
+        "\n",
+        "int main() {\n",
+        "    std::cout << \"1 + 2 = \" << add(1, 2) << std::endl;\n",
+        "    return 0;\n",
+        "}\n"
+    ))?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("run");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("1 + 2 = 3"));
+    project_root
+        .child("target/modules/math.gcm")
+        .assert(predicate::path::is_file());
+
+    Ok(())
+}
+
+#[test]
+fn succeed_with_precompiled_header_and_modules() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(concat!(
+        "[project]\n",
+        "name = \"foo\"\n",
+        "\n",
+        "[build]\n",
+        "precompiled-header = \"src/pch.hpp\"\n"
+    ))?;
+    project_root
+        .child("src/pch.hpp")
+        .write_str("#pragma once\n#include <iostream>\n#define ANSWER 42\n")?;
+    // Module units include the header in their global module fragment.
+    project_root.child("src/foo.cpp").write_str(concat!(
+        "module;\n",
+        "#include \"pch.hpp\"\n",
+        "export module foo;\n",
+        "export int answer() { return ANSWER; }\n"
+    ))?;
+    project_root.child("src/main.cpp").write_str(concat!(
+        "import foo;\n",
+        "\n",
+        "int main() {\n",
+        "    std::cout << \"answer = \" << answer() << std::endl;\n",
+        "    return 0;\n",
+        "}\n"
+    ))?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("run");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("answer = 42"));
+
+    Ok(())
+}
+
+#[test]
+fn succeed_reusing_cached_objects() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let cppargo_home = tmp_dir.child("home");
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(PROJECT_MANIFEST)?;
+    let main_file = project_root.child("src/main.cpp");
+    main_file.write_str(HELLO_WORLD_PROGRAM)?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .env("CPPARGO_HOME", cppargo_home.path())
+        .arg("build");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Running compiler..."));
+    cppargo_home
+        .child("cache")
+        .assert(predicate::path::is_dir());
+
+    // Switching back to sources compiled before reuses their objects.
+    main_file.write_str("int main() { return 1; }\n")?;
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .env("CPPARGO_HOME", cppargo_home.path())
+        .arg("build");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Running compiler..."));
+
+    main_file.write_str(HELLO_WORLD_PROGRAM)?;
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .env("CPPARGO_HOME", cppargo_home.path())
+        .arg("run");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Reusing cached target/obj/src/main.cpp.o",
+        ))
+        .stdout(predicate::str::contains("Running compiler...").not())
+        .stdout(predicate::str::contains("Hello World!"));
+
+    Ok(())
+}
+
+#[test]
+fn succeed_without_or_trimming_object_cache() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let cppargo_home = tmp_dir.child("home");
+    let project_root = tmp_dir.child("foo");
+    project_root
+        .child("Cppargo.toml")
+        .write_str(PROJECT_MANIFEST)?;
+    project_root
+        .child("src/main.cpp")
+        .write_str(HELLO_WORLD_PROGRAM)?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .env("CPPARGO_HOME", cppargo_home.path())
+        .env("CPPARGO_CACHE", "off")
+        .arg("build");
+    cmd.assert().success();
+    cppargo_home
+        .child("cache")
+        .assert(predicate::path::missing());
+
+    // An empty cache keeps nothing to reuse.
+    for _ in 0..2 {
+        let mut cmd = cppargo()?;
+        cmd.current_dir(project_root.path())
+            .env("CPPARGO_HOME", cppargo_home.path())
+            .env("CPPARGO_CACHE_SIZE", "0")
+            .arg("build");
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Running compiler..."))
+            .stdout(predicate::str::contains("Reusing cached").not());
+        std::fs::remove_dir_all(project_root.child("target"))?;
+    }
+
+    // Sizes too large to count in bytes are invalid too.
+    for size in ["1GiB", "99999999999999"] {
+        let mut cmd = cppargo()?;
+        cmd.current_dir(project_root.path())
+            .env("CPPARGO_CACHE_SIZE", size)
+            .arg("build");
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains(format!(
+                "Invalid `CPPARGO_CACHE_SIZE` `{size}`!"
+            )));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn succeed_with_compiler_launcher() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let cppargo_home = tmp_dir.child("home");
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(concat!(
+        "[project]\n",
+        "name = \"foo\"\n",
+        "\n",
+        "[build]\n",
+        "compiler-launcher = \"nice\"\n"
+    ))?;
+    project_root
+        .child("src/main.cpp")
+        .write_str(HELLO_WORLD_PROGRAM)?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .env("CPPARGO_HOME", cppargo_home.path())
+        .arg("build");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\"nice\" \"g++\""));
+    // Compilers run through a launcher, which may cache objects itself,
+    // don't use the object cache.
+    cppargo_home
+        .child("cache")
+        .assert(predicate::path::missing());
+
+    // The environment variable takes precedence over the manifest.
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .env("CPPARGO_HOME", cppargo_home.path())
+        .env("CPPARGO_COMPILER_LAUNCHER", "env CCACHE_DISABLE=1")
+        .arg("build");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "\"env\" \"CCACHE_DISABLE=1\" \"g++\"",
+    ));
+
+    Ok(())
+}
@@ -5,11 +5,11 @@ use common::*;
 fn succeed_create_and_build_project() -> anyhow::Result<()> {
     let tmp_dir = assert_fs::TempDir::new()?;
 
-    let mut cmd = Command::cargo_bin("cppargo")?;
+    let mut cmd = cppargo()?;
     cmd.args(["new", "foo"]).current_dir(tmp_dir.path());
     cmd.assert().success();
 
-    let mut cmd = Command::cargo_bin("cppargo")?;
+    let mut cmd = cppargo()?;
     cmd.current_dir(tmp_dir.child("foo").path()).arg("build");
     cmd.assert()
         .success()
@@ -34,7 +34,7 @@ fn succeed_build_and_run_project() -> anyhow::Result<()> {
     let main_file = project_src.child("main.cpp");
     main_file.write_str(HELLO_WORLD_PROGRAM)?;
 
-    let mut cmd = Command::cargo_bin("cppargo")?;
+    let mut cmd = cppargo()?;
     cmd.current_dir(project_root.path()).arg("run");
     cmd.assert()
         .success()
@@ -42,3 +42,476 @@ fn succeed_build_and_run_project() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn succeed_run_project_printing_its_version() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.child("Cppargo.toml").write_str(concat!(
+        "[project]\n",
+        "name = \"foo\"\n",
+        "version = \"1.2.0\"\n",
+        "license = \"MIT\"\n"
+    ))?;
+    project_root.child("src/main.cpp").write_str(concat!(
+        "#include <iostream>\n",
+        "\n",
+        "static_assert(CPPARGO_PKG_VERSION_MINOR == 2);\n",
+        "\n",
+        "int main() {\n",
+        "    std::cout << CPPARGO_PKG_NAME \" \" CPPARGO_PKG_VERSION \" (\" CPPARGO_PKG_LICENSE \")\\n\";\n",
+        "}\n"
+    ))?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("run");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("foo 1.2.0 (MIT)"));
+
+    Ok(())
+}
+
+#[test]
+fn succeed_build_and_run_example() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+    let project_src = project_root.child("src");
+    project_src.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(PROJECT_MANIFEST)?;
+
+    let main_file = project_src.child("main.cpp");
+    main_file.write_str(HELLO_WORLD_PROGRAM)?;
+    let lib_file = project_src.child("greet.cpp");
+    lib_file.write_str(concat!(
+        "#include <iostream>\n",
+        "\n",
+        "void greet(const char *name) {\n",
+        "    std::cout << \"Hello \" << name << \"!\\n\";\n",
+        "}\n"
+    ))?;
+
+    let example_file = project_root.child("examples/greet_world.cpp");
+    example_file.write_str(concat!(
+        "void greet(const char *name);\n",
+        "\n",
+        "int main() {\n",
+        "    greet(\"Example\");\n",
+        "}\n"
+    ))?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .arg("build")
+        .arg("--examples");
+    cmd.assert().success();
+    project_root
+        .child("target/examples/greet_world")
+        .assert(predicate::path::is_file());
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .arg("run")
+        .arg("--example")
+        .arg("greet_world");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Hello Example!"))
+        .stdout(predicate::str::contains("Hello World!").not());
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .arg("run")
+        .arg("--example")
+        .arg("missing");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Failed to find `missing` in `examples/`!",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn succeed_build_and_run_example_of_library() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("greet");
+    project_root
+        .child("Cppargo.toml")
+        .write_str("[project]\nname = \"greet\"\n")?;
+    project_root.child("src/greet.cpp").write_str(concat!(
+        "#include <iostream>\n",
+        "\n",
+        "void greet(const char *name) {\n",
+        "    std::cout << \"Hello \" << name << \"!\\n\";\n",
+        "}\n"
+    ))?;
+    project_root.child("examples/hello.cpp").write_str(concat!(
+        "void greet(const char *name);\n",
+        "\n",
+        "int main() {\n",
+        "    greet(\"Library\");\n",
+        "}\n"
+    ))?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .arg("build")
+        .arg("--examples");
+    cmd.assert().success();
+    project_root
+        .child("target/examples/hello")
+        .assert(predicate::path::is_file());
+    project_root
+        .child("target/greet")
+        .assert(predicate::path::missing());
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .arg("run")
+        .arg("--example")
+        .arg("hello");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Hello Library!"));
+
+    // Without any program to link, a library is missing its binary.
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("run");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Missing \"src/main.cpp\" file"));
+
+    Ok(())
+}
+
+#[test]
+fn succeed_run_through_runner_with_env() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+    let project_src = project_root.child("src");
+    project_src.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(concat!(
+        "[project]\n",
+        "name = \"foo\"\n",
+        "\n",
+        "[run]\n",
+        "runner = [\"env\", \"WRAPPER=manifest\"]\n",
+        "\n",
+        "[run.env]\n",
+        "GREETING = \"Hello\"\n"
+    ))?;
+
+    let main_file = project_src.child("main.cpp");
+    main_file.write_str(concat!(
+        "#include <cstdlib>\n",
+        "#include <iostream>\n",
+        "\n",
+        "int main() {\n",
+        "    std::cout << std::getenv(\"GREETING\") << \" from \" << std::getenv(\"WRAPPER\") << std::endl;\n",
+        "    return 0;\n",
+        "}\n"
+    ))?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("run");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Hello from manifest"));
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .arg("run")
+        .arg("--runner")
+        .arg("env WRAPPER=cli");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Hello from cli"));
+
+    Ok(())
+}
+
+#[test]
+fn succeed_cross_compile_and_run_through_runner() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+    let project_src = project_root.child("src");
+    project_src.create_dir_all()?;
+
+    // The host compiler stands in for a cross compiler, and `env` for an
+    // emulator, since neither is available everywhere. The general runner
+    // must not stand in for the missing emulator of `aarch64-linux-gnu`.
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(concat!(
+        "[project]\n",
+        "name = \"foo\"\n",
+        "\n",
+        "[run]\n",
+        "runner = [\"env\", \"WRAPPER=manifest\"]\n",
+        "\n",
+        "[target.riscv64-linux-gnu]\n",
+        "compiler = \"g++\"\n",
+        "runner = [\"env\", \"EMULATED=1\"]\n",
+        "\n",
+        "[target.aarch64-linux-gnu]\n",
+        "compiler = \"g++\"\n"
+    ))?;
+
+    let main_file = project_src.child("main.cpp");
+    main_file.write_str(HELLO_WORLD_PROGRAM)?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .arg("run")
+        .arg("--target")
+        .arg("riscv64-linux-gnu");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Hello World!"));
+    project_root
+        .child("target/riscv64-linux-gnu/debug/foo")
+        .assert(predicate::path::is_file());
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .arg("run")
+        .arg("--target")
+        .arg("aarch64-linux-gnu");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Can't run binaries built for `aarch64-linux-gnu` on this host!",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn succeed_install_and_uninstall_project() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let root = tmp_dir.child("root");
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(PROJECT_MANIFEST)?;
+    project_root
+        .child("src/main.cpp")
+        .write_str(HELLO_WORLD_PROGRAM)?;
+    project_root
+        .child("include/foo.hpp")
+        .write_str("#pragma once\n")?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .arg("install")
+        .arg("--root")
+        .arg(root.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("-O3"))
+        .stdout(predicate::str::contains(
+            "Project `foo` installed successfully!",
+        ));
+    root.child("include/foo.hpp")
+        .assert(predicate::path::is_file());
+
+    let mut cmd = Command::new(root.child("bin/foo").path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Hello World!"));
+
+    let mut cmd = cppargo()?;
+    cmd.args(["uninstall", "foo", "--root"]).arg(root.path());
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Project `foo` uninstalled successfully!",
+    ));
+    root.child("bin").assert(predicate::path::missing());
+    root.child("include").assert(predicate::path::missing());
+
+    let mut cmd = cppargo()?;
+    cmd.args(["uninstall", "foo", "--root"]).arg(root.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Project `foo` isn't installed"));
+
+    Ok(())
+}
+
+#[test]
+fn succeed_install_workspace_with_library() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let root = tmp_dir.child("root");
+    let workspace_root = tmp_dir.child("workspace");
+    workspace_root
+        .child("Cppargo.toml")
+        .write_str("[workspace]\nmembers = [\"libs/*\", \"apps/*\"]\n")?;
+    for (dir, name, src_file, source) in [
+        (
+            "libs/core",
+            "core",
+            "src/core.cpp",
+            "int answer() { return 42; }\n",
+        ),
+        ("apps/cli", "cli", "src/main.cpp", HELLO_WORLD_PROGRAM),
+    ] {
+        let member = workspace_root.child(dir);
+        member
+            .child("Cppargo.toml")
+            .write_str(&format!("[project]\nname = \"{name}\"\n"))?;
+        member.child(src_file).write_str(source)?;
+    }
+    workspace_root
+        .child("libs/core/include/core.hpp")
+        .write_str("int answer();\n")?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(workspace_root.path())
+        .arg("install")
+        .arg("--root")
+        .arg(root.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Project `core` installed successfully!",
+        ))
+        .stdout(predicate::str::contains(
+            "Project `cli` installed successfully!",
+        ));
+    root.child("bin/cli").assert(predicate::path::is_file());
+    root.child("lib/libcore.a")
+        .assert(predicate::path::is_file());
+    root.child("include/core.hpp")
+        .assert(predicate::path::is_file());
+    root.child(".installed.json")
+        .assert(predicate::str::contains("lib/libcore.a"));
+
+    Ok(())
+}
+
+#[test]
+fn succeed_clean_workspace_member() -> anyhow::Result<()> {
+    let workspace_root = assert_fs::TempDir::new()?;
+    workspace_root
+        .child("Cppargo.toml")
+        .write_str("[workspace]\nmembers = [\"libs/*\", \"apps/*\"]\n")?;
+    for (dir, name, src_file, source) in [
+        (
+            "libs/core",
+            "core",
+            "src/core.cpp",
+            "int answer() { return 42; }\n",
+        ),
+        ("apps/cli", "cli", "src/main.cpp", HELLO_WORLD_PROGRAM),
+    ] {
+        let member = workspace_root.child(dir);
+        member
+            .child("Cppargo.toml")
+            .write_str(&format!("[project]\nname = \"{name}\"\n"))?;
+        member.child(src_file).write_str(source)?;
+    }
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(workspace_root.path()).arg("build");
+    cmd.assert().success();
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(workspace_root.path())
+        .args(["clean", "-p", "core"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Project cleaned successfully!"));
+    workspace_root
+        .child("target/obj/core")
+        .assert(predicate::path::missing());
+    workspace_root
+        .child("target/obj/cli/src/main.cpp.o")
+        .assert(predicate::path::is_file());
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(workspace_root.path())
+        .args(["clean", "-p", "cli"]);
+    cmd.assert().success();
+    workspace_root
+        .child("target/cli")
+        .assert(predicate::path::missing());
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(workspace_root.child("apps/cli").path())
+        .arg("clean");
+    cmd.assert().success();
+    workspace_root
+        .child("target")
+        .assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn succeed_package_project() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root
+        .child("Cppargo.toml")
+        .write_str(&format!("{PROJECT_MANIFEST}version = \"1.0.0\"\n"))?;
+    project_root
+        .child("src/main.cpp")
+        .write_str(HELLO_WORLD_PROGRAM)?;
+    project_root.child("README.md").write_str("# foo\n")?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("package");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Verifying foo-1.0.0..."))
+        .stdout(predicate::str::contains(
+            "Project `foo-1.0.0` packaged successfully!",
+        ));
+
+    let archive = project_root.child("target/package/foo-1.0.0.tar.gz");
+    let mut cmd = Command::new("tar");
+    cmd.arg("--list")
+        .arg("--gzip")
+        .arg("--file")
+        .arg(archive.path());
+    cmd.assert()
+        .success()
+        .stdout("foo-1.0.0/Cppargo.toml\nfoo-1.0.0/src/main.cpp\n");
+
+    Ok(())
+}
+
+#[test]
+fn fail_verify_package_missing_files() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root
+        .child("Cppargo.toml")
+        .write_str(&format!("{PROJECT_MANIFEST}version = \"1.0.0\"\n"))?;
+    project_root
+        .child("src/main.cpp")
+        .write_str("#include \"../config/config.hpp\"\n\nint main() { return VALUE; }\n")?;
+    project_root
+        .child("config/config.hpp")
+        .write_str("#define VALUE 0\n")?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("package");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to verify"));
+
+    // Extra files are archived with the `[package]` table.
+    project_root.child("Cppargo.toml").write_str(&format!(
+        "{PROJECT_MANIFEST}version = \"1.0.0\"\n[package]\ninclude = [\"config/*.hpp\"]\n"
+    ))?;
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("package");
+    cmd.assert().success();
+
+    Ok(())
+}
@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 pub use assert_cmd::prelude::*;
 pub use assert_fs::prelude::*;
 pub use predicates::prelude::*;
@@ -14,3 +16,15 @@ pub const HELLO_WORLD_PROGRAM: &str = concat!(
 );
 
 pub const PROJECT_MANIFEST: &str = "[project]\nname = \"foo\"\n";
+
+/// The `cppargo` binary, whose home directory, where object files are cached,
+/// is inside the target directory rather than the home directory of the user.
+pub fn cppargo() -> Result<Command, assert_cmd::cargo::CargoError> {
+    let mut cmd = Command::cargo_bin("cppargo")?;
+    cmd.env(
+        "CPPARGO_HOME",
+        std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("cppargo-home"),
+    );
+
+    Ok(cmd)
+}
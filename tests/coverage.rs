@@ -0,0 +1,102 @@
+mod common;
+use common::*;
+
+const MAIN_FILE_WITH_UNCOVERED_BRANCH: &str = concat!(
+    "int main(int argc, char **argv) {\n",
+    "    if (argc > 5) {\n",
+    "        return 1;\n",
+    "    }\n",
+    "    return 0;\n",
+    "}\n"
+);
+
+#[test]
+fn succeed_writing_coverage_reports() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+    let project_src = project_root.child("src");
+    project_src.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(PROJECT_MANIFEST)?;
+
+    let main_file = project_src.child("main.cpp");
+    main_file.write_str(MAIN_FILE_WITH_UNCOVERED_BRANCH)?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("coverage");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "src/main.cpp        4        3    75.00%",
+    ));
+
+    let project_coverage = project_root.child("target/coverage");
+    project_coverage
+        .child("lcov.info")
+        .assert(predicate::str::contains("DA:3,0\n"));
+    project_coverage
+        .child("html/index.html")
+        .assert(predicate::str::contains("src/main.cpp.html"));
+    project_coverage
+        .child("html/src/main.cpp.html")
+        .assert(predicate::path::is_file());
+
+    Ok(())
+}
+
+#[test]
+fn fail_because_coverage_is_under_threshold() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+    let project_src = project_root.child("src");
+    project_src.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(PROJECT_MANIFEST)?;
+
+    let main_file = project_src.child("main.cpp");
+    main_file.write_str(MAIN_FILE_WITH_UNCOVERED_BRANCH)?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path())
+        .arg("coverage")
+        .arg("--fail-under")
+        .arg("90");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Line coverage 75.00% is under the required 90.00%!",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn succeed_running_with_run_env() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("foo");
+    project_root.create_dir_all()?;
+
+    let project_manifest = project_root.child("Cppargo.toml");
+    project_manifest.write_str(concat!(
+        "[project]\n",
+        "name = \"foo\"\n",
+        "\n",
+        "[run.env]\n",
+        "COVERAGE_MODE = \"on\"\n"
+    ))?;
+    project_root.child("src/main.cpp").write_str(concat!(
+        "#include <cstdlib>\n",
+        "#include <cstring>\n",
+        "\n",
+        "int main() {\n",
+        "    const char *mode = std::getenv(\"COVERAGE_MODE\");\n",
+        "    return mode != nullptr && std::strcmp(mode, \"on\") == 0 ? 0 : 1;\n",
+        "}\n"
+    ))?;
+
+    let mut cmd = cppargo()?;
+    cmd.current_dir(project_root.path()).arg("coverage");
+    cmd.assert().success();
+
+    Ok(())
+}
@@ -19,7 +19,7 @@ where
     project_root.assert(predicates::path::is_dir());
     let project_manifest = project_root.child("Cppargo.toml");
     project_manifest.assert(format!(
-        "[project]\nname = \"{}\"\n",
+        "[project]\nname = \"{}\"\nversion = \"0.1.0\"\n",
         project_root.file_name().unwrap().to_str().unwrap()
     ));
     let project_src = project_root.child("src");
@@ -57,7 +57,7 @@ fn succeed_without_vcs() -> anyhow::Result<()> {
     let project_root = tmp_dir.child("foo");
     let project_path = project_root.path();
 
-    let mut cmd = Command::cargo_bin("cppargo")?;
+    let mut cmd = cppargo()?;
     cmd.env("PATH", "").arg("new").arg(project_path);
     cmd.assert()
         .success()
@@ -77,7 +77,7 @@ fn succeed_relative() -> anyhow::Result<()> {
     let project_root = tmp_dir.child("foo");
     let project_path = project_root.path().strip_prefix(tmp_dir.path())?;
 
-    let mut cmd = Command::cargo_bin("cppargo")?;
+    let mut cmd = cppargo()?;
     cmd.current_dir(tmp_dir.path()).arg("new").arg(project_path);
     cmd.assert()
         .success()
@@ -94,7 +94,7 @@ fn succeed_relative_nested() -> anyhow::Result<()> {
     let project_root = tmp_dir.child("foo").child("bar");
     let project_path = project_root.path().strip_prefix(tmp_dir.path())?;
 
-    let mut cmd = Command::cargo_bin("cppargo")?;
+    let mut cmd = cppargo()?;
     cmd.current_dir(tmp_dir.path()).arg("new").arg(project_path);
     cmd.assert()
         .success()
@@ -111,7 +111,7 @@ fn succeed_absolute() -> anyhow::Result<()> {
     let project_root = tmp_dir.child("foo");
     let project_path = project_root.path();
 
-    let mut cmd = Command::cargo_bin("cppargo")?;
+    let mut cmd = cppargo()?;
     cmd.arg("new").arg(project_path);
     cmd.assert()
         .success()
@@ -128,7 +128,7 @@ fn succeed_absolute_nested() -> anyhow::Result<()> {
     let project_root = tmp_dir.child("foo").child("bar");
     let project_path = project_root.path();
 
-    let mut cmd = Command::cargo_bin("cppargo")?;
+    let mut cmd = cppargo()?;
     cmd.arg("new").arg(project_path);
     cmd.assert()
         .success()
@@ -146,7 +146,7 @@ fn fail_because_project_dir_already_exists() -> anyhow::Result<()> {
     existing_dir.create_dir_all()?;
     let project_path = tmp_dir.child("foo").path().to_path_buf();
 
-    let mut cmd = Command::cargo_bin("cppargo")?;
+    let mut cmd = cppargo()?;
     cmd.current_dir(tmp_dir.path())
         .arg("new")
         .arg(&project_path);
@@ -154,3 +154,40 @@ fn fail_because_project_dir_already_exists() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn succeed_with_name() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("2048");
+    let project_path = project_root.path();
+
+    let mut cmd = cppargo()?;
+    cmd.arg("new").arg(project_path).args(["--name", "game"]);
+    cmd.assert()
+        .success()
+        .stdout(success_predicate(project_path));
+
+    project_root
+        .child("Cppargo.toml")
+        .assert("[project]\nname = \"game\"\nversion = \"0.1.0\"\n");
+
+    Ok(())
+}
+
+#[test]
+fn fail_because_project_name_is_invalid() -> anyhow::Result<()> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let project_root = tmp_dir.child("my app");
+    let project_path = project_root.path();
+
+    let mut cmd = cppargo()?;
+    cmd.arg("new").arg(project_path);
+    cmd.assert()
+        .failure()
+        .stderr(fail_predicate(project_path))
+        .stderr(predicate::str::contains("Use `--name my_app`"));
+
+    project_root.assert(predicate::path::missing());
+
+    Ok(())
+}